@@ -0,0 +1,54 @@
+// Generic cycle detection for anything we keep applying the same step to
+//  We remember every state we've seen (the state itself, not a hash of it) so a hash collision
+//  can't make us think we found a cycle when we didn't
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+#[derive(Debug)]
+pub struct CycleInfo {
+    // Steps before we enter the cycle
+    pub tail: u64,
+    // Length of the cycle
+    pub period: u64,
+}
+
+pub struct CycleFinder<S> {
+    // Every state we've seen, in order, history[i] is the state after i steps
+    history: Vec<S>,
+    info: CycleInfo,
+}
+
+impl<S: Clone + Eq + Hash> CycleFinder<S> {
+    // Keep stepping from start until we see a state for the second time
+    pub fn find<F>(start: S, mut step: F) -> CycleFinder<S>
+        where F: FnMut(&S) -> S {
+        let mut seen: HashMap<S, u64> = HashMap::new();
+        let mut history: Vec<S> = Vec::new();
+        let mut state = start;
+        loop {
+            if let Some(first) = seen.get(&state) {
+                let tail = *first;
+                let period = history.len() as u64 - tail;
+                return CycleFinder { history, info: CycleInfo { tail, period } };
+            }
+            seen.insert(state.clone(), history.len() as u64);
+            let next = step(&state);
+            history.push(state);
+            state = next;
+        }
+    }
+
+    pub fn info(&self) -> &CycleInfo {
+        &self.info
+    }
+
+    // The state after n steps, for any n
+    pub fn state_after(&self, n: u64) -> &S {
+        if n < self.history.len() as u64 {
+            return &self.history[n as usize];
+        }
+        let idx = self.info.tail + (n - self.info.tail) % self.info.period;
+        &self.history[idx as usize]
+    }
+}
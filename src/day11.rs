@@ -2,140 +2,92 @@ use crate::{Config, AOCProblem};
 
 #[derive(Debug)]
 struct Star {
-    x: usize,
-    y: usize,
-    index: usize,
-}
-
-impl Star {
-    fn distance_to(&self, star: &Star) -> i32 {
-        // Manhattan distance = |x1 - x2| + |y1 - y2|
-        return (self.x as i32 - star.x as i32).abs() + (self.y as i32 - star.y as i32).abs();
-    }
+    x: u64,
+    y: u64,
 }
 
+// We only keep the star coordinates, empty rows/cols are the ones no star is in
 pub struct Day11 {
-    board: Vec<Vec<char>>,
-    expanded_rows: Vec<usize>,
-    expanded_cols: Vec<usize>,
     stars: Vec<Star>,
-    expansion_coefficient: usize,
+    row_count: u64,
+    // Every empty row/col becomes this many rows/cols
+    expansion_factor: u64,
 }
 
 impl Day11 {
     pub fn new() -> Day11 {
         Day11 {
-            board: Vec::new(),
-            expanded_rows: Vec::new(),
-            expanded_cols: Vec::new(),
             stars: Vec::new(),
-            expansion_coefficient: 1,
+            row_count: 0,
+            expansion_factor: 2,
         }
     }
 
-    // Given x/y, get the position in the expanded galaxy
-    // Count how many expanded rows/cols we pass going from 0 to x and 0 to y
-    fn get_expanded_star(&self, star: &Star) -> Star {
-        let mut e_x = star.x;
-        let mut e_y = star.y;
-        for e_row in &self.expanded_rows {
-            // Sorted order, so once we hit x, we can stop
-            if e_row < &star.y { e_y += &self.expansion_coefficient } else { break; }
-        }
-
-        for e_col in &self.expanded_cols {
-            if e_col < &star.x { e_x += &self.expansion_coefficient } else { break; }
-        }
-
-        Star { x: e_x, y: e_y, index: star.index }
+    // Given the original coordinates on one axis, get the position in the expanded galaxy
+    // The number of empty rows/cols before a star is its coordinate minus the number of distinct
+    //  occupied rows/cols before it
+    fn expand_axis(&self, coords: &[u64]) -> Vec<u64> {
+        let mut occupied: Vec<u64> = coords.to_vec();
+        occupied.sort();
+        occupied.dedup();
+        coords.iter().map(|c| {
+            let occupied_before = occupied.partition_point(|o| o < c) as u64;
+            let empty_before = c - occupied_before;
+            c + empty_before * (self.expansion_factor - 1)
+        }).collect()
     }
 
-    fn expand_galaxy(&mut self) {
-        // Expand every column with all . into two columns with all .
-        // Expand every row with all . into two rows with all .
-        for i in 0..self.board.len() {
-            let mut all_empty = true;
-            for j in 0..self.board[i].len() {
-                if self.board[i][j] != '.' {
-                    all_empty = false;
-                    break;
-                }
-            }
-            if all_empty {
-                self.expanded_rows.push(i);
-            }
-        }
-
-        for i in 0..self.board[0].len() {
-            let mut all_empty = true;
-            for j in 0..self.board.len() {
-                if self.board[j][i] != '.' {
-                    all_empty = false;
-                    break;
-                }
-            }
-            if all_empty {
-                self.expanded_cols.push(i);
-            }
-        }
-
-        for i in 0..self.board.len() {
-            for j in 0..self.board[i].len() {
-                if self.board[i][j] == '#' {
-                    let star = Star {x: j, y: i, index: self.stars.len() + 1 };
-                    let e_star = self.get_expanded_star(&star);
-                    self.stars.push(e_star);
-                }
-            }
+    // Sum of |a - b| over every pair, sort and then each value is bigger than everything before it
+    //  so it contributes value * index - (sum of values before it)
+    fn sum_pairwise_distances(values: &mut [u64]) -> u64 {
+        values.sort();
+        let mut prefix_sum: u64 = 0;
+        let mut dist_sum: u64 = 0;
+        for (i, v) in values.iter().enumerate() {
+            dist_sum += v * i as u64 - prefix_sum;
+            prefix_sum += v;
         }
+        dist_sum
     }
 
-    fn print_expanded_galaxy(&self) {
-        println!("Expanded Rows: {:?}", self.expanded_rows);
-        println!("Expanded Cols: {:?}", self.expanded_cols);
+    // Manhattan distance splits into x and y, so we can sum each axis separately
+    fn sum_star_distances(&self) -> u64 {
+        let xs: Vec<u64> = self.stars.iter().map(|s| s.x).collect();
+        let ys: Vec<u64> = self.stars.iter().map(|s| s.y).collect();
+        let mut e_xs = self.expand_axis(&xs);
+        let mut e_ys = self.expand_axis(&ys);
+        println!("Expanded stars: {:?}", e_xs.iter().zip(e_ys.iter()).collect::<Vec<_>>());
+        Day11::sum_pairwise_distances(&mut e_xs) + Day11::sum_pairwise_distances(&mut e_ys)
     }
+}
 
-    fn print_galaxy(&self) {
-        println!("Galaxy:\n");
-        for i in 0..self.board.len() {
-            let mut board_str = String::new();
-            let row = &self.board[i];
-            for j in 0..row.len() {
-                board_str.push(row[j]);
-            }   
-            println!("{:?}", board_str);
+impl AOCProblem for Day11 {
+    fn configure(&mut self, config: &Config) -> Result<(), String> {
+        // Part b expands each empty row/col into a million, unless we're told otherwise
+        self.expansion_factor = config.get_option("expansion")?
+            .unwrap_or(if config.variant { 1000000 } else { 2 });
+        if self.expansion_factor < 1 {
+            return Err("expansion has to be at least 1".to_string());
         }
+        Ok(())
     }
-}
 
-impl AOCProblem for Day11 {
     fn handle_line(&mut self, line: &str, _config: &Config) {
-        let row: Vec<char> = line.chars().collect();
-        self.board.push(row);
-    }
-    
-    // Just count the items in the list
-    fn compute_a(&mut self) -> String {
-        self.expand_galaxy();
-        self.print_galaxy();
-        self.print_expanded_galaxy();
-        let mut dist_sum: i64 = 0;
-        for i in 0..self.stars.len() {
-            let star = self.stars.get(i).unwrap();
-            for j in (i+1)..self.stars.len() {
-                let star2 = self.stars.get(j).unwrap();
-                println!("Star1 {:?} Star2 {:?}", &star, &star2);
-                let dist = star.distance_to(star2);
-                println!("Shortest distance between {} and {} = {}", i, j, dist);
-                dist_sum += dist as i64;
+
+        for (x, c) in line.chars().enumerate() {
+            if c == '#' {
+                self.stars.push(Star { x: x as u64, y: self.row_count });
             }
         }
-        dist_sum.to_string()
+        self.row_count += 1;
+    }
+
+    fn compute_a(&mut self) -> String {
+        println!("{} stars, expansion factor {}", self.stars.len(), self.expansion_factor);
+        self.sum_star_distances().to_string()
     }
 
     fn compute_b(&mut self) -> String {
-        // Coefficient is number of rows/cols to add - 1, since we keep the original        
-        self.expansion_coefficient = 999999;
-        return self.compute_a();
+        self.compute_a()
     }
 }
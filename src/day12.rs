@@ -1,218 +1,352 @@
 use crate::{Config, AOCProblem};
-use std::collections::HashMap;
+use std::fs;
 
-#[derive(Debug)]
-// For part B we need to cache.
-//  If we're trying to place block N, and the earliest place we can put it is location M
-//  We can remember (M,N,#combos), so the next time we're trying to place N at M we just look up combos
+// A single nonogram line, the row of known/unknown cells and the block sizes that have to fit in it
+#[derive(Debug, Clone)]
 struct PicrossRow {
     row: Vec<char>,
     counts: Vec<usize>,
-    cache: HashMap<u64, u64>,
-}
-
-// Search item, where in the row are we, and which block are we trying to place
-// These will be entered into a work queue for the search process
-#[derive(Debug)]
-struct WorkItem {
-    index: usize,
-    block: usize,
-    placements: Vec<usize>, // Where we put each block (starting)
-    combos: u64,
 }
 
 impl PicrossRow {
-    // Can we place block # block_index starting at position start?
-    // If so, where's the first place we can put the next block (1 beyond the end of this block)
-    fn place_block(&mut self, block_size: usize, start: usize) -> Option<usize> {
-        // Find the first spot starting at i that we can put block (block_index)
-        let mut block_pos = None;
-        for i in start..self.row.len() {
-           if self.fit_block_here(i, block_size) {
-                block_pos = Some(i);
-                break;
-           }
-           if self.row[i] == '#' {
-                // We don't have a choice, next block has to start here
-                // But we couldn't fit it, so this fails
-                return None;
-           }
-        }
-        match block_pos {
-            None => None,
-            Some(pos) => Some(pos),
-        }
-    }
-
-    fn fit_block_here(&mut self, index: usize, size: usize) -> bool {
+    // Repeat the row factor times with a ? between copies, and repeat the counts to match
+    fn unfold(&self, factor: usize) -> PicrossRow {
+        let mut row: Vec<char> = Vec::new();
+        let mut counts: Vec<usize> = Vec::new();
+        for i in 0..factor {
+            if i > 0 { row.push('?'); }
+            row.extend_from_slice(&self.row);
+            counts.extend_from_slice(&self.counts);
+        }
+        PicrossRow { row, counts }
+    }
+
+    // Can a block of this size start at index?
+    //  Every cell in the block can't be known empty, and the next space has to be empty or the end
+    fn fit_block_here(&self, index: usize, size: usize) -> bool {
         if index+size > self.row.len() { return false; }
-        for i in 0..size {
-            if self.row[index+i] == '.' { return false; }
-        }
-        // Next space has to be empty or the end
-        let after_end = index+size;
-        if after_end < self.row.len() {
-            if self.row[after_end] == '#' {
-                return false;
+        if self.row[index..index+size].contains(&'.') { return false; }
+        !(index+size < self.row.len() && self.row[index+size] == '#')
+    }
+
+    // Dynamic programming over (position, block index)
+    //  table[i][b] = number of ways to fill row[i..] with blocks b..
+    //  There's one extra row at the end so placing a block that ends the row can skip its trailing space
+    fn combo_table(&self) -> Vec<Vec<u128>> {
+        let n = self.row.len();
+        let m = self.counts.len();
+        let mut table: Vec<Vec<u128>> = vec![vec![0; m + 1]; n + 2];
+        table[n][m] = 1;
+        table[n + 1][m] = 1;
+        for i in (0..n).rev() {
+            for b in (0..=m).rev() {
+                let mut combos: u128 = 0;
+                // Leave this space empty
+                if self.row[i] != '#' {
+                    combos += table[i + 1][b];
+                }
+                // Or start block b here, and skip the space after it
+                if b < m && self.fit_block_here(i, self.counts[b]) {
+                    combos += table[i + self.counts[b] + 1][b + 1];
+                }
+                table[i][b] = combos;
             }
         }
-        return true;
+        table
     }
 
-    fn _print_solution(&self, item: &WorkItem) {
+    // How many combos can work for this row?
+    fn count_all_combos(&self) -> u128 {
+        self.combo_table()[0][0]
+    }
+
+    // Walk the table from the start of the row to build one arrangement
+    //  Whenever both are possible, choose gets the number of combos for leaving the space empty and
+    //  for placing the next block here, and returns true to place the block
+    fn build_arrangement<F>(&self, table: &[Vec<u128>], mut choose: F) -> Vec<char>
+        where F: FnMut(u128, u128) -> bool {
         let mut soln: Vec<char> = vec!['.'; self.row.len()];
-        println!("Solution: {:?}", item);
-        for i in 0..self.counts.len() {
-            let size = self.counts[i];
-            let start = item.placements[i];
-            for j in start..(start+size) {
-                soln[j] = '#';
+        let mut i = 0;
+        let mut b = 0;
+        while i < self.row.len() {
+            let empty = if self.row[i] != '#' { table[i + 1][b] } else { 0 };
+            let place = if b < self.counts.len() && self.fit_block_here(i, self.counts[b]) {
+                table[i + self.counts[b] + 1][b + 1]
+            } else { 0 };
+            if place > 0 && (empty == 0 || choose(empty, place)) {
+                for cell in soln.iter_mut().skip(i).take(self.counts[b]) {
+                    *cell = '#';
+                }
+                i += self.counts[b] + 1;
+                b += 1;
+            } else {
+                i += 1;
             }
         }
-        let mut board_str = String::new();
-        for k in 0..soln.len() {
-            board_str.push(soln[k]);
+        soln
+    }
+
+    // Every valid arrangement, up to limit of them
+    fn arrangements(&self, limit: usize) -> Vec<Vec<char>> {
+        let table = self.combo_table();
+        let total = table[0][0];
+        let mut solns: Vec<Vec<char>> = Vec::new();
+        // The kth arrangement (in order of placing blocks as late as possible first), by skipping
+        //  over the combos on the branch we don't take
+        for k in 0..total.min(limit as u128) {
+            let mut remaining = k;
+            solns.push(self.build_arrangement(&table, |empty, _place| {
+                if remaining < empty { false } else { remaining -= empty; true }
+            }));
         }
-        println!("{:?}", board_str);
+        solns
     }
 
-    // How many combos can work for this row?
-    fn count_all_combos(&mut self) -> u64 {
-        let mut count = 0;
-        let first_block_earliest = self.place_block(self.counts[0], 0);
-        let first_block;
-        match first_block_earliest {
-            None => return 0,
-            Some(f) => first_block = f,
-        }
-        let mut w1 = WorkItem {index: first_block, block: 0, placements: Vec::new(), combos: 0};
-        count += w1.count_combos(self);
-        if self.row[first_block] == '?' {
-            // We don't have to start here, could start later
-            for k in first_block+1..(self.row.len() - self.counts[0] + 1) {
-                if self.row[k] == '?' || self.row[k] == '#' {
-                    // We could start here too, maybe
-                    let mut new_item = WorkItem { index: k, block: 0, placements: Vec::new(), combos: 0 };
-                    count += new_item.count_combos(self);                    
-                    if self.row[k] == '#' {
-                        // This was the last place we can start
-                        break;
-                    }
-                }
-            }
+    // Uniformly pick a random arrangement, weighting each choice by how many combos it leads to
+    fn sample(&self, rng: &mut XorShift) -> Option<Vec<char>> {
+        let table = self.combo_table();
+        if table[0][0] == 0 { return None; }
+        Some(self.build_arrangement(&table, |empty, place| rng.next_below(empty + place) >= empty))
+    }
+
+    // Line solving, fill in every ? that's the same in all arrangements
+    //  None if nothing fits, that's a contradiction
+    fn line_solve(&self) -> Option<Vec<char>> {
+        if self.count_all_combos() == 0 { return None; }
+        let mut solved = self.row.clone();
+        let mut test = self.clone();
+        for (i, cell) in solved.iter_mut().enumerate() {
+            if *cell != '?' { continue; }
+            test.row[i] = '#';
+            let filled = test.count_all_combos();
+            test.row[i] = '.';
+            let empty = test.count_all_combos();
+            test.row[i] = '?';
+            if filled == 0 { *cell = '.'; }
+            if empty == 0 { *cell = '#'; }
         }
-        count
+        Some(solved)
     }
 }
 
-impl WorkItem {
-    fn get_hash_key(&self) -> u64 {
-        // Hash this workItem using block and index only
-        // Since we only have 2 numbers, a simple x*1000 + y will work, as long as y will be less than 1000
-        return (self.index * 1000 + self.block) as u64;
-    }
-    fn count_combos(&mut self, row: &mut PicrossRow) -> u64 {
-        let key = self.get_hash_key();
-        if let Some(cached_combos) = row.cache.get(&key) {
-            //println!("\tCache Hit: {:?}/{} = {}", self, key, cached_combos);
-            return *cached_combos;
-        }
-        
-        //println!("Working on {:?}", self);
-        let mut count = 0;
-        let next_block_size = row.counts[self.block];
-        let valid = row.fit_block_here(self.index, next_block_size);
-        if valid {
-            let block_start = self.index;
-            self.placements.push(block_start);
-            let s_block = self.block + 1;
-            if s_block >= row.counts.len() {
-                // We placed the last block, this is a complete answer if there are no more #'s
-                let mut no_extra = true;
-                for k in block_start+next_block_size..row.row.len() {
-                    if row.row[k] == '#' {
-                        // There's another block! this doesn't work
-                        no_extra = false;
-                        break;
-                    }
+// A full nonogram, row and column clues for a grid
+//  Each row and column is a PicrossRow, we line solve them until nothing changes, then guess
+struct Nonogram {
+    row_clues: Vec<Vec<usize>>,
+    col_clues: Vec<Vec<usize>>,
+}
+
+impl Nonogram {
+    // Clues are one line each, blocks separated by commas like the puzzle input, 0 for an empty line
+    //  A line with "rows" starts the row clues, "cols" starts the column clues, # is a comment
+    fn parse(contents: &str) -> Result<Nonogram, String> {
+        let mut nonogram = Nonogram { row_clues: Vec::new(), col_clues: Vec::new() };
+        let mut in_rows: Option<bool> = None;
+        for line in contents.lines().map(|l| l.trim()) {
+            if line.is_empty() || line.starts_with('#') { continue; }
+            match line {
+                "rows" => { in_rows = Some(true); continue; },
+                "cols" => { in_rows = Some(false); continue; },
+                _ => (),
+            }
+            let mut clue: Vec<usize> = Vec::new();
+            for token in line.split(',') {
+                match token.trim().parse::<usize>() {
+                    Ok(0) => (),
+                    Ok(n) => clue.push(n),
+                    Err(_) => return Err(format!("Bad clue: {}", line)),
                 }
-                if no_extra {
-                    count += 1;
-                    // row.print_solution(&self);
+            }
+            match in_rows {
+                Some(true) => nonogram.row_clues.push(clue),
+                Some(false) => nonogram.col_clues.push(clue),
+                None => return Err("Expecting rows or cols before the clues".to_string()),
+            }
+        }
+        if nonogram.row_clues.is_empty() || nonogram.col_clues.is_empty() {
+            return Err("Need both row and column clues".to_string());
+        }
+        Ok(nonogram)
+    }
+
+    // Line solve every row and column until nothing changes, false if we hit a contradiction
+    fn propagate(&self, grid: &mut [Vec<char>]) -> bool {
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (r, clue) in self.row_clues.iter().enumerate() {
+                let line = PicrossRow { row: grid[r].clone(), counts: clue.clone() };
+                match line.line_solve() {
+                    None => return false,
+                    Some(solved) => {
+                        if solved != grid[r] { changed = true; grid[r] = solved; }
+                    },
                 }
-            } else {
-                // Need to place another block, earliest it can go is 2 spaces after the end of this one
-                //  (leave 1 empty space between blocks)
-                let next_start = block_start + next_block_size + 1;
-                for k in next_start..row.row.len() {
-                    if row.row[k] == '.' { continue; } // Can't start on a known empty
-                    let mut new_work_item = WorkItem {index: k, block: s_block, placements: self.placements.clone(), combos: 0};
-                    count += new_work_item.count_combos(row);
-                    if row.row[k] == '#' { break; } // Have to start the next one here at least
+            }
+            for (c, clue) in self.col_clues.iter().enumerate() {
+                let line = PicrossRow { row: grid.iter().map(|row| row[c]).collect(), counts: clue.clone() };
+                match line.line_solve() {
+                    None => return false,
+                    Some(solved) => {
+                        for (r, cell) in solved.iter().enumerate() {
+                            if grid[r][c] != *cell { changed = true; grid[r][c] = *cell; }
+                        }
+                    },
                 }
             }
         }
-        //println!("\t Combos for {:?} = {}", self, count);
-        row.cache.insert(key, count);
-        self.combos = count;
-        return count;
+        true
+    }
+
+    // Propagate, and if that doesn't finish the grid, guess the first unknown cell and backtrack
+    fn solve(&self, mut grid: Vec<Vec<char>>) -> Option<Vec<Vec<char>>> {
+        if !self.propagate(&mut grid) { return None; }
+        let unknown = grid.iter().enumerate()
+            .find_map(|(r, row)| row.iter().position(|c| *c == '?').map(|c| (r, c)));
+        match unknown {
+            None => Some(grid),
+            Some((r, c)) => {
+                for guess in ['#', '.'] {
+                    let mut next = grid.clone();
+                    next[r][c] = guess;
+                    if let Some(solved) = self.solve(next) {
+                        return Some(solved);
+                    }
+                }
+                None
+            },
+        }
+    }
+}
+
+fn row_string(row: &[char]) -> String {
+    row.iter().collect()
+}
+
+// Small random number generator for sampling arrangements, so we don't need another crate
+struct XorShift {
+    state: u64,
+}
+
+impl XorShift {
+    fn next(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+
+    // Random number in 0..bound, close enough to uniform for our purposes
+    fn next_below(&mut self, bound: u128) -> u128 {
+        let r = ((self.next() as u128) << 64) | self.next() as u128;
+        r % bound
     }
 }
 
 pub struct Day12 {
     rows: Vec<PicrossRow>,
+    // How many arrangements to print for each row, enumerated in order or randomly sampled
+    enumerate: usize,
+    sample: usize,
+    seed: u64,
+    // How many copies of each row to join together
+    unfold: usize,
+    // Solve a full nonogram from this file instead of the picross rows
+    nonogram: Option<String>,
 }
 
 impl Day12 {
     pub fn new() -> Day12 {
         Day12 {
             rows: Vec::new(),
+            enumerate: 0,
+            sample: 0,
+            seed: 1,
+            unfold: 1,
+            nonogram: None,
+        }
+    }
+
+    fn solve_nonogram(&self, filename: &str) -> String {
+        let contents = match fs::read_to_string(filename) {
+            Ok(c) => c,
+            Err(e) => { eprintln!("Unable to read {}: {}", filename, e); return "Error".to_string(); },
+        };
+        let nonogram = match Nonogram::parse(&contents) {
+            Ok(n) => n,
+            Err(e) => { eprintln!("Unable to parse {}: {}", filename, e); return "Error".to_string(); },
+        };
+        println!("Nonogram with {} rows and {} cols", nonogram.row_clues.len(), nonogram.col_clues.len());
+        let grid = vec![vec!['?'; nonogram.col_clues.len()]; nonogram.row_clues.len()];
+        match nonogram.solve(grid) {
+            Some(solved) => {
+                let rows: Vec<String> = solved.iter().map(|r| row_string(r)).collect();
+                rows.join("\n")
+            },
+            None => "No solution".to_string(),
         }
     }
 }
 
 impl AOCProblem for Day12 {
-    fn handle_line(&mut self, line: &str, config: &Config) {
+    fn configure(&mut self, config: &Config) -> Result<(), String> {
+        self.enumerate = config.get_option("enumerate")?.unwrap_or(0);
+        self.sample = config.get_option("sample")?.unwrap_or(0);
+        self.seed = config.get_option("seed")?.unwrap_or(1);
+        if self.seed == 0 {
+            // xorshift gets stuck on 0
+            return Err("seed can't be 0".to_string());
+        }
+        self.nonogram = config.get_option("nonogram")?;
+        // Part b unfolds each row 5 times, unless we're told otherwise
+        self.unfold = config.get_option("unfold")?.unwrap_or(if config.variant { 5 } else { 1 });
+        if self.unfold < 1 {
+            return Err("unfold has to be at least 1".to_string());
+        }
+        Ok(())
+    }
+
+    fn handle_line(&mut self, line: &str, _config: &Config) {
         let mut line_iter = line.split_whitespace();
 
         // Two components, initial row and counts
-        let mut init_row = line_iter.next().unwrap();
+        let init_row = line_iter.next().unwrap();
         let init_counts = line_iter.next().unwrap();
-        let mut counts: Vec<usize> = init_counts.split(",").map(|x| x.parse::<usize>().unwrap()).collect();
-        let mut row_str = String::from(init_row);
-
-        if config.variant {
-            for _i in 0..4 {
-                row_str.push('?');
-                row_str.push_str(init_row);
+        let counts: Vec<usize> = init_counts.split(",").map(|x| x.parse::<usize>().unwrap()).collect();
 
-            }
-            init_row = &row_str;
-
-            let orig_counts = counts.clone();
-            for _i in 0..4 {
-                let mut c2 = orig_counts.clone();
-                counts.append(&mut c2);
-            }
-        }
-
-        let puzzle_row = PicrossRow {
+        let mut puzzle_row = PicrossRow {
             row: init_row.chars().collect(),
             counts,
-            cache: HashMap::new(),
         };
+
+        if self.unfold > 1 {
+            puzzle_row = puzzle_row.unfold(self.unfold);
+        }
         println!("Picross Row: {:?}", puzzle_row);
         self.rows.push(puzzle_row);
     }
     
     // Just count the items in the list
     fn compute_a(&mut self) -> String {
-        let mut val: u64 = 0;
-        for item in &mut self.rows {
+        if let Some(filename) = &self.nonogram {
+            return self.solve_nonogram(filename);
+        }
+        let mut val: u128 = 0;
+        let mut rng = XorShift { state: self.seed };
+        for item in &self.rows {
             println!("Counting Combos for {:?}", item);
             let combos = item.count_all_combos();
             println!("Combos: {}", combos);
             val += combos;
+
+            for soln in item.arrangements(self.enumerate) {
+                println!("\tArrangement: {}", row_string(&soln));
+            }
+            for _ in 0..self.sample {
+                if let Some(soln) = item.sample(&mut rng) {
+                    println!("\tSampled: {}", row_string(&soln));
+                }
+            }
         }
         val.to_string()
     }
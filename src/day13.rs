@@ -6,85 +6,59 @@ struct Pattern {
     vert_reflect: Option<usize>,
 }
 
-impl Pattern {
-    fn compute_vert_reflect(&mut self) -> bool {
-        // Is there a vertical column reflection?
-        for i in 0..self.board[0].len() - 1 {
-            if let Some(orig) = self.vert_reflect {
-                if orig == i {
-                    // Already found a vert reflect here, must be on part b
-                    // Keep looking
-                    continue;
-                }
-            }
-            if self.is_vert_reflect(i) {
-                self.vert_reflect = Some(i);
-                println!("Found Vertical reflection at {}", i);
-                return true;
-            }
-        }
-        return false;
-    }
+// Number of cells that differ if we reflect across the line between index and index+1
+//  Lines are bitmasks, # is a 1 bit, so the differences are the 1 bits in a ^ b
+fn count_mismatches(lines: &[u64], index: usize) -> u32 {
+    (0..=index).rev().zip(index+1..lines.len())
+        .map(|(a, b)| (lines[a] ^ lines[b]).count_ones())
+        .sum()
+}
 
-    // Is the line between col and col+1 a reflection?
-    fn is_vert_reflect(&self, col: usize) -> bool {
-        //println!("Checking vert reflect for {}", col);
-        for i in 0..self.board[0].len() {
-            if i > col {
-                return true;
-            }
-            let left_col = col - i;
-            let right_col = col + 1 + i;
+// Every reflection line with exactly k differences, k = 0 is a perfect mirror, k = 1 has one smudge
+fn find_reflections(lines: &[u64], smudges: u32) -> Vec<usize> {
+    (0..lines.len().saturating_sub(1))
+        .filter(|i| count_mismatches(lines, *i) == smudges)
+        .collect()
+}
 
-            if right_col >= self.board[0].len() {
-                return true;
-            }
-            for j in 0..self.board.len() {
-                if self.board[j][left_col] != self.board[j][right_col] {
-                    return false;
-                }
-            }
-        }
-        return true;
+impl Pattern {
+    // Bitmask for each row, bit j is column j
+    fn row_masks(&self) -> Vec<u64> {
+        self.board.iter()
+            .map(|row| row.iter().enumerate().fold(0, |m, (j, c)| if *c == '#' { m | 1 << j } else { m }))
+            .collect()
     }
 
-    fn compute_horiz_reflect(&mut self) -> bool {
-        // Is there a horizontal row reflection?
-        for i in 0..self.board.len() - 1 {
-            if let Some(orig) = self.horiz_reflect {
-                if orig == i {
-                    // already found this one, keep looking, must be on part b
-                    continue;
-                }
-            }
-            if self.is_horiz_reflect(i) {
-                self.horiz_reflect = Some(i);
-                println!("Found Horizontal reflection at {}", i);
-                return true;
+    // Bitmask for each column, bit i is row i
+    fn col_masks(&self) -> Vec<u64> {
+        let mut masks: Vec<u64> = vec![0; self.board[0].len()];
+        for (i, row) in self.board.iter().enumerate() {
+            for (j, c) in row.iter().enumerate() {
+                if *c == '#' { masks[j] |= 1 << i; }
             }
         }
-        return false;
+        masks
     }
 
-    // Is the line between col and col+1 a reflection?
-    fn is_horiz_reflect(&self, row: usize) -> bool {
-        //println!("Checking horiz_reflect for {}", row);
-        for i in 0..self.board.len() {
-            if i > row {
-                return true;
-            }
-            let up_row: usize = row - i;
-            let down_row = row + 1 + i;
-            if down_row >= self.board.len() {
-                return true;
-            }
-            for j in 0..self.board[0].len() {
-                if self.board[up_row][j] != self.board[down_row][j] {
-                    return false;
-                }
-            }
+    // Find the reflection with exactly this many smudges, columns first
+    fn compute_reflect(&mut self, smudges: u32) -> bool {
+        if self.board.len() > 64 || self.board[0].len() > 64 {
+            eprintln!("Pattern is too big for 64 bit masks");
+            return false;
+        }
+        let vert = find_reflections(&self.col_masks(), smudges);
+        let horiz = find_reflections(&self.row_masks(), smudges);
+        if vert.len() + horiz.len() > 1 {
+            println!("Found more than one reflection: vertical {:?} horizontal {:?}", vert, horiz);
         }
-        return true;
+        self.vert_reflect = vert.first().copied();
+        self.horiz_reflect = if self.vert_reflect.is_none() { horiz.first().copied() } else { None };
+        if let Some(v) = self.vert_reflect {
+            println!("Found Vertical reflection at {}", v);
+        } else if let Some(h) = self.horiz_reflect {
+            println!("Found Horizontal reflection at {}", h);
+        }
+        self.vert_reflect.is_some() || self.horiz_reflect.is_some()
     }
 
     fn print_board(&self) {
@@ -98,60 +72,21 @@ impl Pattern {
             println!("{:?}", board_str);
         }
     }
-
-    // Part B, let's brute force it
-    // Likely a way to be smarter about which rows/columns to try
-    //    But brute forcing doesn't seem so bad, there's no exponential growth
-    fn find_smudge(&mut self) -> bool {
-        let orig_horiz_reflect = self.horiz_reflect;
-        let orig_vert_reflect = self.vert_reflect;
-
-        for i in 0..self.board.len() {
-            for j in 0..self.board[i].len() {
-                //println!("Trying smudge at {},{}", i,j);
-                if self.board[i][j] == '.' {
-                    self.board[i][j] = '#';
-                } else {
-                    self.board[i][j] = '.';
-                }
-                if self.compute_vert_reflect() {
-                    if self.vert_reflect != orig_vert_reflect {
-                        self.horiz_reflect = None;
-                        println!("Found differnet vert reflection with smudge at {} {}: {:?} {:?}", i,j, self.vert_reflect, self.horiz_reflect);
-                        return true;
-                    } 
-                }
-                if self.compute_horiz_reflect() {
-                    if self.horiz_reflect != orig_horiz_reflect && self.horiz_reflect != None {
-                        self.vert_reflect = None;
-                        println!("Found differnet horiz reflection with smudge at {} {}: {:?} {:?}", i,j, self.vert_reflect, self.horiz_reflect);
-                        return true;
-                    }
-                }
-                // Reset the changes
-                self.horiz_reflect = orig_horiz_reflect;
-                self.vert_reflect = orig_vert_reflect;
-                if self.board[i][j] == '.' {
-                    self.board[i][j] = '#';
-                } else {
-                    self.board[i][j] = '.';
-                }
-            }
-        }
-        return false;
-    }
 }
 
 pub struct Day13 {
     patterns: Vec<Pattern>,
     cur_pattern: Pattern,
+    // How many cells have to differ across the mirror, 0 for part a and 1 for part b
+    smudges: u32,
 }
 
 impl Day13 {
     pub fn new() -> Day13 {
         Day13 {
             patterns: Vec::new(),
-            cur_pattern: Pattern {board: Vec::new(), horiz_reflect: None, vert_reflect: None}
+            cur_pattern: Pattern {board: Vec::new(), horiz_reflect: None, vert_reflect: None},
+            smudges: 0,
         }
     }
     
@@ -173,6 +108,11 @@ impl Day13 {
 }
 
 impl AOCProblem for Day13 {
+    fn configure(&mut self, config: &Config) -> Result<(), String> {
+        self.smudges = config.get_option("smudges")?.unwrap_or(if config.variant { 1 } else { 0 });
+        Ok(())
+    }
+
     fn handle_line(&mut self, line: &str, _config: &Config) {
         if line.len() == 0 {
             // Complete the latest pattern
@@ -184,37 +124,22 @@ impl AOCProblem for Day13 {
         self.cur_pattern.board.push(row);
     }
 
-    // Just count the items in the list
     fn compute_a(&mut self) -> String {
         let new_pattern = Pattern {board: Vec::new(), horiz_reflect: None, vert_reflect: None};
         self.patterns.push(std::mem::replace(&mut self.cur_pattern, new_pattern));
             
-        let mut idx = 0;
-        for pattern in &mut self.patterns {
-            println!("Handling Pattern: {}", idx);
+        for (idx, pattern) in self.patterns.iter_mut().enumerate() {
+            println!("Handling Pattern: {} with {} smudges", idx, self.smudges);
             pattern.print_board();
-            pattern.compute_vert_reflect();
-            if pattern.vert_reflect == None {
-                pattern.compute_horiz_reflect();
+            if !pattern.compute_reflect(self.smudges) {
+                eprintln!("Unable to find a reflection on pattern {}", idx);
             }
-            idx += 1;
         }
         self.compute_score().to_string()
     }
 
+    // Part b is the same search with one smudge
     fn compute_b(&mut self) -> String {
-        // Find the original reflections
-        self.compute_a();
-        let mut idx = 0;
-        for pattern in &mut self.patterns {
-            println!("Looking for smudge on pattern {}", idx);
-            println!("\n Original reflection {:?} {:?}", pattern.horiz_reflect, pattern.vert_reflect);
-            if !pattern.find_smudge() {
-                pattern.print_board();
-                panic!("Unable to find a smudge on this board!");
-            }
-            idx += 1;
-        }
-        self.compute_score().to_string()
+        self.compute_a()
     }
 }
@@ -1,183 +1,160 @@
 use crate::{Config, AOCProblem};
-use std::collections::hash_map::DefaultHasher;
-use std::hash::{Hash, Hasher};
-use std::collections::HashMap;
+use crate::cycle::CycleFinder;
 
-pub struct Day14 {
-    board: Vec<Vec<char>>,
-}
+#[derive(Clone, Copy)]
+enum Tilt { North, West, South, East }
 
-fn print_board(board: &Vec<Vec<char>>) {
-    println!("Board:\n");
-    for i in 0..board.len() {
-        let foo = &board[i].iter().collect::<String>();
-        println!("{:?}", foo);
-    }
+// Rocks packed as bits, one u128 per row, bit j is column j
+//  Cube rocks never move, so they're shared by every state, round rocks are the state
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct RockBoard {
+    width: usize,
+    height: usize,
+    round: Vec<u128>,
+    cubes: Vec<u128>,
 }
 
-// Could hash the board state as n bit integers for each row where 1 bits are positions of the O
-//  But of course, we have a 100x100 grid, which is too long
-//  Could try a smarter hash function, but we'll try just creating the full state string
-fn board_hash_str(board: &Vec<Vec<char>>) -> u64 {
-    let mut str = String::new();
-    for i in 0..board.len() {
-        let foo = &board[i].iter().collect::<String>();
-        str.push_str(foo);
+impl RockBoard {
+    fn parse(lines: &[String]) -> Result<RockBoard, &'static str> {
+        let width = lines.first().map_or(0, |l| l.len());
+        if width > 128 {
+            return Err("Board is too wide for 128 bit rows");
+        }
+        let mut board = RockBoard { width, height: lines.len(), round: Vec::new(), cubes: Vec::new() };
+        for line in lines {
+            let mut round: u128 = 0;
+            let mut cubes: u128 = 0;
+            for (j, c) in line.chars().enumerate() {
+                match c {
+                    'O' => round |= 1 << j,
+                    '#' => cubes |= 1 << j,
+                    _ => (),
+                }
+            }
+            board.round.push(round);
+            board.cubes.push(cubes);
+        }
+        Ok(board)
     }
-    let mut hasher = DefaultHasher::new();
-    str.hash(&mut hasher);
-    hasher.finish()
-}
 
-fn tilt_north(board: &Vec<Vec<char>>) -> Vec<Vec<char>> {
-    let mut tilted = board.clone();
-    for i in 1..tilted.len() {
-        for j in 0..tilted[i].len() {
-            if tilted[i][j] != 'O' { continue; } // Only move rocks
-            let mut moved = i;
-            for k in 1..i+1 {
-                // move up k spots max until we hit a O or #
-                if tilted[i-k][j] == '.' { moved = i-k } else { break; }
-            }
-            tilted[i][j] = '.';
-            tilted[moved][j] = 'O';
+    // The kth cell along a line, in the order rocks pile up when tilted this way
+    fn cell(&self, dir: Tilt, line: usize, k: usize) -> (usize, usize) {
+        match dir {
+            Tilt::North => (k, line),
+            Tilt::South => (self.height - 1 - k, line),
+            Tilt::West => (line, k),
+            Tilt::East => (line, self.width - 1 - k),
         }
     }
-    tilted
-}
 
-fn tilt_west(board: &Vec<Vec<char>>) -> Vec<Vec<char>> {
-    let mut tilted = board.clone();
-    for j in 1..tilted[0].len() {
-        for i in 0..tilted.len() {
-            if tilted[i][j] != 'O' { continue; } // Only move rocks
-            let mut moved = j;
-            for k in 1..j+1 {
-                // move up k spots max until we hit a O or #
-                if tilted[i][j-k] == '.' { moved = j-k } else { break; }
+    // Roll every round rock as far as it goes, in place
+    //  Walk each line in the direction of the tilt, remembering the next spot a rock would land on
+    fn tilt(&mut self, dir: Tilt) {
+        let (lines, len) = match dir {
+            Tilt::North | Tilt::South => (self.width, self.height),
+            Tilt::West | Tilt::East => (self.height, self.width),
+        };
+        for line in 0..lines {
+            let mut dest = 0;
+            for k in 0..len {
+                let (i, j) = self.cell(dir, line, k);
+                if self.cubes[i] & (1 << j) != 0 {
+                    dest = k + 1;
+                } else if self.round[i] & (1 << j) != 0 {
+                    self.round[i] &= !(1 << j);
+                    let (di, dj) = self.cell(dir, line, dest);
+                    self.round[di] |= 1 << dj;
+                    dest += 1;
+                }
             }
-            tilted[i][j] = '.';
-            tilted[i][moved] = 'O';
         }
     }
-    tilted
-}
 
-fn tilt_south(board: &Vec<Vec<char>>) -> Vec<Vec<char>> {
-    let mut tilted = board.clone();
-    let tlen = tilted.len();
-    for i in 1..tlen {
-        for j in 0..tilted[i].len() {
-            if tilted[tlen-i-1][j] != 'O' { continue; } // Only move rocks
-            let mut moved = tlen-i-1;
-            for k in 1..i+1 {
-                // move up k spots max until we hit a O or #
-                if tilted[tlen-i-1+k][j] == '.' { moved = tlen-i-1+k } else { break; }
-            }
-            tilted[tlen-i-1][j] = '.';
-            tilted[moved][j] = 'O';
+    fn tilt_cycle(&mut self) {
+        for dir in [Tilt::North, Tilt::West, Tilt::South, Tilt::East] {
+            self.tilt(dir);
         }
     }
-    tilted
-}
 
-fn tilt_east(board: &Vec<Vec<char>>) -> Vec<Vec<char>> {
-    let mut tilted = board.clone();
-    let tlen = tilted[0].len();
-    for j in 1..tlen {
-        for i in 0..tilted.len() {
-            if tilted[i][tlen-j-1] != 'O' { continue; } // Only move rocks
-            let mut moved = tlen-j-1;
-            for k in 1..j+1 {
-                // move up k spots max until we hit a O or #
-                if tilted[i][tlen-j-1+k] == '.' { moved = tlen-j-1+k } else { break; }
-            }
-            tilted[i][tlen-j-1] = '.';
-            tilted[i][moved] = 'O';
+    // Each round rock scores the number of rows from it to the south edge, inclusive
+    fn score_north(&self) -> usize {
+        self.round.iter().enumerate()
+            .map(|(i, r)| r.count_ones() as usize * (self.height - i))
+            .sum()
+    }
+
+    fn print_board(&self) {
+        println!("Board:\n");
+        for i in 0..self.height {
+            let row: String = (0..self.width).map(|j| {
+                if self.round[i] & (1 << j) != 0 { 'O' }
+                else if self.cubes[i] & (1 << j) != 0 { '#' }
+                else { '.' }
+            }).collect();
+            println!("{:?}", row);
         }
     }
-    tilted
 }
 
+pub struct Day14 {
+    lines: Vec<String>,
+    // How many spin cycles to run for part b
+    cycles: u64,
+}
 
 impl Day14 {
     pub fn new() -> Day14 {
         Day14 {
-            board: Vec::new(),
+            lines: Vec::new(),
+            cycles: 1000000000,
         }
     }
 
-    fn score_north(&self, tilted: Vec<Vec<char>>) -> usize {
-        let mut score: usize = 0;
-        for i in 0..tilted.len() {
-            let row_score: usize = tilted.len() - i;
-            for j in 0..tilted[i].len() {
-                if tilted[i][j] == 'O' {
-                    score += row_score;
-                }
-            }
+    fn build_board(&self) -> Option<RockBoard> {
+        match RockBoard::parse(&self.lines) {
+            Ok(board) => Some(board),
+            Err(e) => { eprintln!("{}", e); None },
         }
-        score
-    }
-
-    fn tilt_cycle(&self, tilted: Vec<Vec<char>>) -> Vec<Vec<char>> {
-        let mut cycle: Vec<Vec<char>> = tilt_north(&tilted);
-        cycle = tilt_west(&cycle);
-        cycle = tilt_south(&cycle);
-        cycle = tilt_east(&cycle);
-        cycle
     }
-
 }
 
 impl AOCProblem for Day14 {
+    fn configure(&mut self, config: &Config) -> Result<(), String> {
+        self.cycles = config.get_option("cycles")?.unwrap_or(1000000000);
+        Ok(())
+    }
+
     fn handle_line(&mut self, line: &str, _config: &Config) {
-        let row: Vec<char> = line.chars().collect();
-        self.board.push(row);
+        self.lines.push(line.to_string());
     }
-    
-    // Just count the items in the list
+
     fn compute_a(&mut self) -> String {
-        print_board(&self.board);
-        let tilted = tilt_north(&self.board);
-        print_board(&tilted);
-        println!("Board Size: {} x {}", self.board.len(), self.board[0].len());
-        self.score_north(tilted).to_string()
+        let mut board = match self.build_board() {
+            Some(b) => b,
+            None => return "Error".to_string(),
+        };
+        board.print_board();
+        board.tilt(Tilt::North);
+        board.print_board();
+        println!("Board Size: {} x {}", board.height, board.width);
+        board.score_north().to_string()
     }
 
     fn compute_b(&mut self) -> String {
-        print_board(&self.board);
-        // Board state hash -> cycle number when we saw that board
-        let mut cache: HashMap<u64, u64> = HashMap::new();
-        let mut tilted = self.board.clone();
-        let mut after_cycle: u64 = 0;
-        let mut i = 0;
-        while after_cycle == 0 {
-            tilted = self.tilt_cycle(tilted);
-            i += 1;
-            let h = board_hash_str(&tilted);
-            if let Some(first_cycle) = cache.get(&h) {
-                println!("{} FOUND CYCLE at {}", i, *first_cycle);
-                print_board(&tilted);
-                let cycle_len = i - *first_cycle;
-                let togo = 1000000000 - i;
-                let rem = togo / cycle_len;
-                println!("Cycle len {} Iterations to go after cycles {}", cycle_len, rem);
-                after_cycle = togo - (rem * cycle_len);
-            } else {
-                cache.insert(h, i);
-            }
-            if i % 1000 == 0 {
-                println!("Cycle: {}", i);
-            }
-            //print_board(&tilted);
-        }
-        println!("After Cycle left to go {}", after_cycle);
-        for _ in 0..after_cycle {
-            tilted = self.tilt_cycle(tilted);
-        }
-        print_board(&tilted);
-        self.score_north(tilted).to_string()
+        let board = match self.build_board() {
+            Some(b) => b,
+            None => return "Error".to_string(),
+        };
+        board.print_board();
+        let finder = CycleFinder::find(board, |b| {
+            let mut next = b.clone();
+            next.tilt_cycle();
+            next
+        });
+        println!("Found cycle: {:?}", finder.info());
+        let tilted = finder.state_after(self.cycles);
+        println!("After {} cycles:", self.cycles);
+        tilted.print_board();
+        tilted.score_north().to_string()
     }
-
 }
@@ -1,4 +1,5 @@
 use crate::{Config, AOCProblem};
+use std::fmt;
 
 #[derive(Debug)]
 struct Lens {
@@ -6,6 +7,44 @@ struct Lens {
     focal: u8,
 }
 
+// A single step of the initialization sequence
+#[derive(Debug)]
+enum Operation {
+    Insert(Lens),
+    Remove(String),
+}
+
+// Parse a step like rn=1 or cm-, the label has to be letters and the focal length has to be digits
+fn parse_step(step: &str) -> Result<Operation, String> {
+    let (label, focal) = match step.find(['=', '-']) {
+        Some(idx) => (&step[..idx], &step[idx..]),
+        None => return Err("Missing = or - operation".to_string()),
+    };
+    if label.is_empty() {
+        return Err("Missing label".to_string());
+    }
+    if !label.chars().all(|c| c.is_ascii_alphabetic()) {
+        return Err(format!("Label {} has to be letters only", label));
+    }
+    if let Some(extra) = focal.strip_prefix('-') {
+        if !extra.is_empty() {
+            return Err(format!("Unexpected {} after -", extra));
+        }
+        return Ok(Operation::Remove(label.to_string()));
+    }
+    let digits = &focal[1..];
+    if digits.is_empty() {
+        return Err("Missing focal length".to_string());
+    }
+    if !digits.chars().all(|c| c.is_ascii_digit()) {
+        return Err(format!("Focal length {} has to be digits only", digits));
+    }
+    match digits.parse::<u8>() {
+        Ok(f) => Ok(Operation::Insert(Lens { label: label.to_string(), focal: f })),
+        Err(e) => Err(format!("Focal length {}: {}", digits, e)),
+    }
+}
+
 #[derive(Debug)]
 struct LensBox {
     index: u8,
@@ -64,9 +103,22 @@ impl LensBox {
     }
 }
 
+// Box 0: [rn 1] [cm 2], the same as the puzzle
+impl fmt::Display for LensBox {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Box {}:", self.index)?;
+        for lens in &self.lenses {
+            write!(f, " [{} {}]", lens.label, lens.focal)?;
+        }
+        Ok(())
+    }
+}
+
 pub struct Day15 {
     steps: Vec<String>,
     boxes: Vec<Box<LensBox>>,
+    // Print the boxes after every step
+    trace: bool,
 }
 
 impl Day15 {
@@ -75,7 +127,7 @@ impl Day15 {
         for i in 0..=255 {
             boxes.push(Box::new(LensBox{index: i, lenses: Vec::new()}));
         }
-        Day15 { steps: Vec::new(), boxes}
+        Day15 { steps: Vec::new(), boxes, trace: false }
     }
 
     fn add_lens(&mut self, box_idx: usize, lens: Lens) {
@@ -86,6 +138,15 @@ impl Day15 {
         self.boxes[box_idx].remove_lens(lens);
     }
 
+    // Print every box that has a lens in it, in the puzzle's format
+    fn print_boxes(&self, step: &str) {
+        println!("After \"{}\":", step);
+        for b in self.boxes.iter().filter(|b| !b.lenses.is_empty()) {
+            println!("{}", b);
+        }
+        println!();
+    }
+
     fn focusing_power(&self) -> u64 {
         let mut fp = 0;
         for b in &self.boxes {
@@ -111,6 +172,11 @@ fn hash_char(c: char, val: u64) -> u64 {
 }
 
 impl AOCProblem for Day15 {
+    fn configure(&mut self, config: &Config) -> Result<(), String> {
+        self.trace = config.get_option("trace")?.unwrap_or(false);
+        Ok(())
+    }
+
     fn handle_line(&mut self, line: &str, _config: &Config) {
         let line_iter = line.split(',');
         for token in line_iter {
@@ -131,22 +197,33 @@ impl AOCProblem for Day15 {
     }
 
     fn compute_b(&mut self) -> String {
+        // Parse everything first, so a bad step doesn't leave us half way through the sequence
+        let mut ops: Vec<Operation> = Vec::new();
+        for (idx, item) in self.steps.iter().enumerate() {
+            match parse_step(item) {
+                Ok(op) => ops.push(op),
+                Err(e) => {
+                    eprintln!("Unable to parse step {} \"{}\": {}", idx + 1, item, e);
+                    return "Error".to_string();
+                },
+            }
+        }
+
         let steps = self.steps.clone();
-        for item in steps {
-            if item.contains('=') {
-                let op: Vec<&str> = item.split('=').collect();
-                let label = String::from(op[0]);
-                let step_val = hash(&label) as usize;
-                let focal = op[1].parse::<u8>().unwrap();
-                let new_lens = Lens{label, focal};
-                self.add_lens(step_val, new_lens);
-            } else if item.contains('-') {
-                let op: Vec<&str> = item.split('-').collect();
-                let label = String::from(op[0]);
-                let step_val = hash(&label) as usize;
-                self.remove_lens(step_val, &label);
+        for (item, op) in steps.iter().zip(ops) {
+            match op {
+                Operation::Insert(new_lens) => {
+                    let step_val = hash(&new_lens.label) as usize;
+                    self.add_lens(step_val, new_lens);
+                },
+                Operation::Remove(label) => {
+                    let step_val = hash(&label) as usize;
+                    self.remove_lens(step_val, &label);
+                },
+            }
+            if self.trace {
+                self.print_boxes(item);
             }
-            //println!("After {}: {:?}", item, self.boxes);
         }
         return self.focusing_power().to_string();
     }
@@ -1,8 +1,231 @@
 use crate::{Config, AOCProblem};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 enum Cardinal { EAST, WEST, NORTH, SOUTH }
 
+impl Cardinal {
+    fn index(&self) -> usize {
+        match self {
+            Cardinal::NORTH => 0,
+            Cardinal::SOUTH => 1,
+            Cardinal::EAST => 2,
+            Cardinal::WEST => 3,
+        }
+    }
+
+    fn from_index(idx: usize) -> Cardinal {
+        match idx {
+            0 => Cardinal::NORTH,
+            1 => Cardinal::SOUTH,
+            2 => Cardinal::EAST,
+            _ => Cardinal::WEST,
+        }
+    }
+}
+
+// Which ways does a beam leave a tile, going dir when it came in
+fn out_dirs(tile: char, dir: Cardinal) -> Vec<Cardinal> {
+    match (tile, dir) {
+        ('/', Cardinal::EAST) => vec![Cardinal::NORTH],
+        ('/', Cardinal::NORTH) => vec![Cardinal::EAST],
+        ('/', Cardinal::SOUTH) => vec![Cardinal::WEST],
+        ('/', Cardinal::WEST) => vec![Cardinal::SOUTH],
+        ('\\', Cardinal::EAST) => vec![Cardinal::SOUTH],
+        ('\\', Cardinal::NORTH) => vec![Cardinal::WEST],
+        ('\\', Cardinal::SOUTH) => vec![Cardinal::EAST],
+        ('\\', Cardinal::WEST) => vec![Cardinal::NORTH],
+        ('|', Cardinal::EAST | Cardinal::WEST) => vec![Cardinal::NORTH, Cardinal::SOUTH],
+        ('-', Cardinal::NORTH | Cardinal::SOUTH) => vec![Cardinal::EAST, Cardinal::WEST],
+        _ => vec![dir],
+    }
+}
+
+// Energized cells as a bitset, bit y*width+x
+type CellSet = Vec<u64>;
+
+fn set_cell(set: &mut CellSet, cell: usize) {
+    set[cell / 64] |= 1 << (cell % 64);
+}
+
+fn union_cells(set: &mut CellSet, other: &CellSet) {
+    for (a, b) in set.iter_mut().zip(other) {
+        *a |= b;
+    }
+}
+
+fn count_cells(set: &CellSet) -> usize {
+    set.iter().map(|w| w.count_ones() as usize).sum()
+}
+
+// Beam propagation precomputed as a graph
+//  A node is a beam entering a mirror or splitter tile going some direction
+//  Its cells are the tile plus everything the outgoing beams light up before hitting the next tile,
+//  and its edges are the (tile, direction) nodes those beams run into
+//  Loops between mirrors are strongly connected components, so we collapse those and then the energized
+//  set for a node is its component's cells plus everything reachable from it
+struct BeamGraph {
+    width: usize,
+    height: usize,
+    cells: Vec<Vec<usize>>,
+    edges: Vec<Vec<usize>>,
+    // Component for each node, and every cell energized starting from that component
+    node_comp: Vec<usize>,
+    comp_reach: Vec<CellSet>,
+}
+
+impl BeamGraph {
+    fn node_id(&self, x: usize, y: usize, dir: Cardinal) -> usize {
+        (y * self.width + x) * 4 + dir.index()
+    }
+
+    fn step(&self, x: usize, y: usize, dir: Cardinal) -> Option<(usize, usize)> {
+        match dir {
+            Cardinal::EAST if x + 1 < self.width => Some((x + 1, y)),
+            Cardinal::WEST if x > 0 => Some((x - 1, y)),
+            Cardinal::NORTH if y > 0 => Some((x, y - 1)),
+            Cardinal::SOUTH if y + 1 < self.height => Some((x, y + 1)),
+            _ => None,
+        }
+    }
+
+    // Walk straight from (x, y) going dir until we hit a tile or leave the board
+    //  Returns the empty cells along the way, and the node for the tile we hit
+    fn trace(&self, board: &[Vec<char>], x: usize, y: usize, dir: Cardinal) -> (Vec<usize>, Option<usize>) {
+        let mut cells: Vec<usize> = Vec::new();
+        let mut pos = Some((x, y));
+        while let Some((cx, cy)) = pos {
+            if board[cy][cx] != '.' {
+                return (cells, Some(self.node_id(cx, cy, dir)));
+            }
+            cells.push(cy * self.width + cx);
+            pos = self.step(cx, cy, dir);
+        }
+        (cells, None)
+    }
+
+    fn build(board: &[Vec<char>]) -> BeamGraph {
+        let height = board.len();
+        let width = board[0].len();
+        let mut graph = BeamGraph {
+            width, height,
+            cells: vec![Vec::new(); width * height * 4],
+            edges: vec![Vec::new(); width * height * 4],
+            node_comp: Vec::new(),
+            comp_reach: Vec::new(),
+        };
+        for y in 0..height {
+            for x in 0..width {
+                if board[y][x] == '.' { continue; }
+                for d in 0..4 {
+                    let dir = Cardinal::from_index(d);
+                    let node = graph.node_id(x, y, dir);
+                    let mut cells = vec![y * width + x];
+                    let mut edges = Vec::new();
+                    for od in out_dirs(board[y][x], dir) {
+                        if let Some((nx, ny)) = graph.step(x, y, od) {
+                            let (c, next) = graph.trace(board, nx, ny, od);
+                            cells.extend(c);
+                            edges.extend(next);
+                        }
+                    }
+                    graph.cells[node] = cells;
+                    graph.edges[node] = edges;
+                }
+            }
+        }
+        graph.condense();
+        graph
+    }
+
+    // Tarjan's strongly connected components, without recursion so big loops don't blow the stack
+    //  Tarjan finishes a component only after every component it can reach, so we can fill in
+    //  the reachable cells as each component is finished
+    fn condense(&mut self) {
+        let n = self.edges.len();
+        let words = (self.width * self.height).div_ceil(64);
+        let unvisited = usize::MAX;
+        let mut index = vec![unvisited; n];
+        let mut low = vec![0; n];
+        let mut on_stack = vec![false; n];
+        let mut stack: Vec<usize> = Vec::new();
+        let mut next_index = 0;
+        self.node_comp = vec![unvisited; n];
+        self.comp_reach.clear();
+
+        for start in 0..n {
+            if index[start] != unvisited || self.cells[start].is_empty() { continue; }
+            // (node, next edge to look at)
+            let mut call_stack: Vec<(usize, usize)> = vec![(start, 0)];
+            index[start] = next_index;
+            low[start] = next_index;
+            next_index += 1;
+            stack.push(start);
+            on_stack[start] = true;
+
+            while let Some((v, ei)) = call_stack.pop() {
+                if ei < self.edges[v].len() {
+                    call_stack.push((v, ei + 1));
+                    let w = self.edges[v][ei];
+                    if index[w] == unvisited {
+                        index[w] = next_index;
+                        low[w] = next_index;
+                        next_index += 1;
+                        stack.push(w);
+                        on_stack[w] = true;
+                        call_stack.push((w, 0));
+                    } else if on_stack[w] {
+                        low[v] = low[v].min(index[w]);
+                    }
+                    continue;
+                }
+                if let Some((parent, _)) = call_stack.last() {
+                    low[*parent] = low[*parent].min(low[v]);
+                }
+                if low[v] == index[v] {
+                    // v is the root of a component, everything above it on the stack is in it
+                    let comp = self.comp_reach.len();
+                    let mut members: Vec<usize> = Vec::new();
+                    while let Some(w) = stack.pop() {
+                        on_stack[w] = false;
+                        self.node_comp[w] = comp;
+                        members.push(w);
+                        if w == v { break; }
+                    }
+                    let mut reach: CellSet = vec![0; words];
+                    for m in &members {
+                        for c in &self.cells[*m] {
+                            set_cell(&mut reach, *c);
+                        }
+                        for w in &self.edges[*m] {
+                            let wc = self.node_comp[*w];
+                            if wc != comp {
+                                union_cells(&mut reach, &self.comp_reach[wc]);
+                            }
+                        }
+                    }
+                    self.comp_reach.push(reach);
+                }
+            }
+        }
+    }
+
+    // How many cells does a beam starting at (x, y) going dir energize
+    fn energized_from(&self, board: &[Vec<char>], x: usize, y: usize, dir: Cardinal) -> usize {
+        let (cells, node) = self.trace(board, x, y, dir);
+        let mut reach: CellSet = match node {
+            Some(n) => self.comp_reach[self.node_comp[n]].clone(),
+            None => vec![0; (self.width * self.height).div_ceil(64)],
+        };
+        for c in cells {
+            set_cell(&mut reach, c);
+        }
+        count_cells(&reach)
+    }
+}
+
 /* Fill in the board with chars depicting how many beams in a square and what directions
  * . : No beam, empty
  * > : EAST
@@ -28,7 +251,10 @@ enum Cardinal { EAST, WEST, NORTH, SOUTH }
  */
 pub struct Day16 {
     board: Vec<Vec<char>>,
-    energized: Vec<Vec<char>>,
+    // Part b uses the beam graph, unless this is set and we simulate each entry point
+    simulate: bool,
+    // How many threads to spread the part b entry points over
+    threads: usize,
 }
 
 fn print_board(board: &Vec<Vec<char>>) {
@@ -50,10 +276,77 @@ impl Day16 {
     pub fn new() -> Day16 {
         Day16 {
             board: Vec::new(),
-            energized: Vec::new(),
+            simulate: false,
+            threads: 1,
         }
     }
 
+    // Every edge cell, pointed into the board
+    fn entry_points(&self) -> Vec<(usize, usize, Cardinal)> {
+        let height = self.board.len();
+        let width = self.board[0].len();
+        let mut entries = Vec::new();
+        for y in 0..height {
+            entries.push((0, y, Cardinal::EAST));
+            entries.push((width - 1, y, Cardinal::WEST));
+        }
+        for x in 0..width {
+            entries.push((x, 0, Cardinal::SOUTH));
+            entries.push((x, height - 1, Cardinal::NORTH));
+        }
+        entries
+    }
+
+    // Evaluate every entry point on a pool of threads, each thread grabs the next entry until they're gone
+    //  Returns the best entry and how many cells it energizes, ties go to the earliest entry so the
+    //  answer doesn't depend on which thread finished first
+    fn best_entry<F>(&self, energized: F) -> Option<((usize, usize, Cardinal), usize)>
+        where F: Fn(usize, usize, Cardinal) -> usize + Sync {
+        let entries = self.entry_points();
+        let next = AtomicUsize::new(0);
+        // (entry index, energized count)
+        let best: Mutex<Option<(usize, usize)>> = Mutex::new(None);
+        thread::scope(|scope| {
+            for _ in 0..self.threads.max(1) {
+                scope.spawn(|| {
+                    loop {
+                        let idx = next.fetch_add(1, Ordering::Relaxed);
+                        if idx >= entries.len() { break; }
+                        let (x, y, dir) = entries[idx];
+                        let e = energized(x, y, dir);
+                        let mut best = best.lock().unwrap();
+                        if best.is_none_or(|(best_idx, max)| e > max || (e == max && idx < best_idx)) {
+                            println!("New Max {} from row {} col {} {:?}", e, y, x, dir);
+                            *best = Some((idx, e));
+                        }
+                    }
+                });
+            }
+        });
+        best.into_inner().unwrap().map(|(idx, e)| (entries[idx], e))
+    }
+}
+
+// State for a single beam simulation, so runs from different entry points don't share anything
+struct BeamRun<'a> {
+    board: &'a [Vec<char>],
+    energized: Vec<Vec<char>>,
+}
+
+impl<'a> BeamRun<'a> {
+    fn new(board: &'a [Vec<char>]) -> BeamRun<'a> {
+        let mut run = BeamRun { board, energized: Vec::new() };
+        run.init_energized();
+        run
+    }
+
+    // Run the full simulation from one entry point
+    fn energized_from(board: &'a [Vec<char>], x: usize, y: usize, dir: Cardinal) -> usize {
+        let mut run = BeamRun::new(board);
+        run.propagate(Beam{x, y, dir});
+        run.count_energized()
+    }
+
     fn init_energized(&mut self) {
         self.energized.clear();
         for i in 0..self.board.len() {
@@ -225,6 +518,20 @@ impl Day16 {
 }
 
 impl AOCProblem for Day16 {
+    fn configure(&mut self, config: &Config) -> Result<(), String> {
+        self.simulate = match config.get_option::<String>("method")?.as_deref() {
+            None | Some("graph") => false,
+            Some("simulate") => true,
+            Some(m) => return Err(format!("method has to be graph or simulate, not {}", m)),
+        };
+        self.threads = config.get_option("threads")?
+            .unwrap_or_else(|| thread::available_parallelism().map_or(1, |n| n.get()));
+        if self.threads < 1 {
+            return Err("threads has to be at least 1".to_string());
+        }
+        Ok(())
+    }
+
     fn handle_line(&mut self, line: &str, _config: &Config) {
         let row: Vec<char> = line.chars().collect();
         self.board.push(row);
@@ -232,47 +539,29 @@ impl AOCProblem for Day16 {
     
     // Just count the items in the list
     fn compute_a(&mut self) -> String {
-        self.init_energized();
         print_board(&self.board);
+        let mut run = BeamRun::new(&self.board);
         let start_beam = Beam{x: 0, y: 0, dir: Cardinal::EAST};
-        self.propagate(start_beam);        
-        print_board(&self.energized);        
-        self.count_energized().to_string()
+        run.propagate(start_beam);
+        print_board(&run.energized);
+        run.count_energized().to_string()
     }
 
     fn compute_b(&mut self) -> String {
-        // Start EAST
-        let mut max = 0;
-        for i in 0..self.board.len() {
-            self.init_energized();
-            let start_beam = Beam{x: 0, y: i, dir: Cardinal::EAST};
-            self.propagate(start_beam);
-            let e = self.count_energized();
-            if e > max { println!("New Max {} from row {} EAST", e, i); max = e; };
-
-            // And WEST
-            self.init_energized();
-            let start_beam = Beam{x: self.board[i].len() - 1, y: i, dir: Cardinal::WEST};
-            self.propagate(start_beam);
-            let e = self.count_energized();
-            if e > max { println!("New Max {} from row {} WEST", e, i); max = e; };
-        }
-
-        // SOUTH
-        for i in 0..self.board[0].len() {
-            self.init_energized();
-            let start_beam = Beam{x: i, y: 0, dir: Cardinal::SOUTH};
-            self.propagate(start_beam);
-            let e = self.count_energized();
-            if e > max { println!("New Max {} from col {} SOUTH", e, i); max = e; };
-
-            // And NORTH
-            self.init_energized();
-            let start_beam = Beam{x: i, y: self.board[i].len() - 1, dir: Cardinal::NORTH};
-            self.propagate(start_beam);
-            let e = self.count_energized();
-            if e > max { println!("New Max {} from col {} NORTH", e, i); max = e; };
+        let best = if self.simulate {
+            // Brute force, run the full beam simulation from every entry point
+            self.best_entry(|x, y, dir| BeamRun::energized_from(&self.board, x, y, dir))
+        } else {
+            let graph = BeamGraph::build(&self.board);
+            println!("Beam graph has {} components", graph.comp_reach.len());
+            self.best_entry(|x, y, dir| graph.energized_from(&self.board, x, y, dir))
+        };
+        match best {
+            Some(((x, y, dir), max)) => {
+                println!("Best entry is row {} col {} going {:?}, energizing {}", y, x, dir, max);
+                max.to_string()
+            },
+            None => "Error".to_string(),
         }
-        return max.to_string();
     }
 }
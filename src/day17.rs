@@ -1,5 +1,7 @@
 use crate::{Config, AOCProblem};
-use pathfinding::prelude::astar;
+use crate::shortest_path::dijkstra_buckets;
+use pathfinding::prelude::{astar, dijkstra};
+use std::time::Instant;
 
 #[derive(Debug, Eq, Hash, Clone, Copy)]
 enum Cardinal { EAST, WEST, NORTH, SOUTH, NONE, ANY }
@@ -38,7 +40,24 @@ struct Node {
 
 pub struct Day17 {
     board: Vec<Vec<u8>>,
-    variant: bool,
+    // How far the crucible has to move in a straight line before turning, and how far it can go
+    min_steps: usize,
+    max_steps: usize,
+    // Start and goal cells as (x, y), the goal defaults to the bottom right corner
+    start: (usize, usize),
+    goal: Option<(usize, usize)>,
+    // Run Dijkstra as well as A*, to make sure the heuristic isn't overestimating
+    compare: bool,
+    // Which search to use for the answer: astar, dijkstra, or buckets
+    algorithm: String,
+    // Time every search against each other
+    bench: bool,
+}
+
+// Parse a cell given as x,y
+fn parse_cell(cell: &str) -> Option<(usize, usize)> {
+    let (x, y) = cell.split_once(',')?;
+    Some((x.trim().parse().ok()?, y.trim().parse().ok()?))
 }
 
 fn print_board(board: &Vec<Vec<u8>>) {
@@ -57,22 +76,45 @@ impl Day17 {
     pub fn new() -> Day17 {
         Day17 {
             board: Vec::new(),
-            variant: false,
+            min_steps: 1,
+            max_steps: 3,
+            start: (0, 0),
+            goal: None,
+            compare: false,
+            algorithm: "buckets".to_string(),
+            bench: false,
         }
     }
 
+    fn on_board(&self, (x, y): (usize, usize)) -> bool {
+        y < self.board.len() && x < self.board[y].len()
+    }
+
+    fn start_location(&self) -> Location {
+        Location{x: self.start.0, y: self.start.1, came_from: Cardinal::NONE}
+    }
+
+    fn goal_location(&self) -> Location {
+        let row_count = self.board.len();
+        let (x, y) = self.goal.unwrap_or((self.board[row_count-1].len() - 1, row_count - 1));
+        Location{x, y, came_from: Cardinal::ANY}
+    }
+
+    fn min_block_cost(&self) -> i32 {
+        self.board.iter().flatten().min().copied().unwrap_or(0) as i32
+    }
+
+    // Manhattan distance, times the cheapest block so it never overestimates, even with 0 cost blocks
+    fn heuristic(p: &Location, goal: &Location, min_cost: i32) -> i32 {
+        ((p.x as i32 - goal.x as i32).abs() + (p.y as i32 - goal.y as i32).abs()) * min_cost
+    }
+
     // A*: Get nodes we can move to from here, and where we came from
     fn get_successors(&self, pos: &Location) -> Vec<Node> {
         let mut next_nodes: Vec<Node> = Vec::new();
         
-        let min_steps: usize;
-        let max_steps: usize;
-        // Part a: we can move 1-3
-        // part b: we can move 4-10
-        match self.variant {
-            false => { min_steps = 1; max_steps = 3},
-            true => { min_steps = 4; max_steps = 10},
-        }
+        let min_steps = self.min_steps;
+        let max_steps = self.max_steps;
 
         // Can go up 1-3
         // Can't go back, and can't go any farther in the old direction
@@ -143,27 +185,151 @@ impl Day17 {
         next_nodes
     }
 
-    fn search(&self) -> i32 {
-        let start = Location{x: 0, y: 0, came_from: Cardinal::NONE};
-        let row_count = self.board.len();
-        let goal = Location{x: self.board[row_count-1].len() - 1, y: row_count - 1, came_from: Cardinal::ANY};
-        let result = astar(
+    fn search(&self) -> Option<(Vec<Location>, i32)> {
+        let start = self.start_location();
+        let goal = self.goal_location();
+        let min_cost = self.min_block_cost();
+        astar(
             &start,
             |p| self.get_successors(p).iter().map(|s| (s.pos, s.cost)).collect::<Vec<_>>(),
-            |p| ((p.x as i32 - goal.x as i32).abs() + (p.y as i32 - goal.y as i32).abs()),
-            |p| *p==goal);
-        let result = result.expect("Can't get to the goal!!");
-        for path in result.0 {
-            println!("{:?}", path);
+            |p| Day17::heuristic(p, &goal, min_cost),
+            |p| *p==goal)
+    }
+
+    fn search_dijkstra(&self) -> Option<(Vec<Location>, i32)> {
+        let start = self.start_location();
+        let goal = self.goal_location();
+        dijkstra(
+            &start,
+            |p| self.get_successors(p).iter().map(|s| (s.pos, s.cost)).collect::<Vec<_>>(),
+            |p| *p==goal)
+    }
+
+    // Dijkstra with our own bucket queue, since the weights are small
+    //  The most a single move can cost is max_steps blocks of the most expensive block
+    fn search_buckets(&self) -> Option<(Vec<Location>, i32)> {
+        let start = self.start_location();
+        let goal = self.goal_location();
+        let max_block = self.board.iter().flatten().max().copied().unwrap_or(0) as usize;
+        dijkstra_buckets(
+            &start,
+            self.max_steps * max_block,
+            |p| self.get_successors(p).iter().map(|s| (s.pos, s.cost as usize)).collect::<Vec<_>>(),
+            |p| *p==goal)
+            .map(|(path, cost)| (path, cost as i32))
+    }
+
+    fn run_search(&self, algorithm: &str) -> Option<(Vec<Location>, i32)> {
+        match algorithm {
+            "astar" => self.search(),
+            "dijkstra" => self.search_dijkstra(),
+            _ => self.search_buckets(),
+        }
+    }
+
+    // Time each search, and make sure they all agree
+    fn bench(&self) {
+        let mut costs: Vec<Option<i32>> = Vec::new();
+        for algorithm in ["astar", "dijkstra", "buckets"] {
+            let timer = Instant::now();
+            let result = self.run_search(algorithm);
+            let elapsed = timer.elapsed();
+            let cost = result.map(|r| r.1);
+            println!("{:>8}: cost {:?} in {:?}", algorithm, cost, elapsed);
+            costs.push(cost);
+        }
+        if costs.windows(2).any(|c| c[0] != c[1]) {
+            eprintln!("Searches disagree on the cost!");
+        }
+    }
+
+    // Check the A* heuristic against Dijkstra
+    //  The costs have to match, and along the optimal path the heuristic can't be more than the cost left
+    fn compare_search(&self, astar_cost: i32) -> bool {
+        let (path, cost) = match self.search_dijkstra() {
+            Some(r) => r,
+            None => { eprintln!("Dijkstra can't get to the goal!!"); return false; },
+        };
+        println!("Dijkstra cost: {}, A* cost: {}", cost, astar_cost);
+        let goal = self.goal_location();
+        let min_cost = self.min_block_cost();
+        let mut spent = 0;
+        let mut admissible = true;
+        for (i, loc) in path.iter().enumerate() {
+            if i > 0 {
+                let prev = &path[i - 1];
+                spent += self.get_successors(prev).iter()
+                    .find(|n| n.pos == *loc && n.pos.came_from == loc.came_from)
+                    .map_or(0, |n| n.cost);
+            }
+            let h = Day17::heuristic(loc, &goal, min_cost);
+            if h > cost - spent {
+                eprintln!("Heuristic {} overestimates at {:?}, only {} left", h, loc, cost - spent);
+                admissible = false;
+            }
+        }
+        admissible && cost == astar_cost
+    }
+
+    // Draw the path over the heat map, with arrows for the direction we moved into each block
+    fn print_path(&self, path: &[Location]) {
+        let mut overlay: Vec<Vec<char>> = self.board.iter()
+            .map(|row| row.iter().map(|c| char::from_digit(*c as u32, 10).unwrap_or('?')).collect())
+            .collect();
+        for step in path.windows(2) {
+            let (from, to) = (&step[0], &step[1]);
+            let (mut x, mut y) = (from.x, from.y);
+            while (x, y) != (to.x, to.y) {
+                let arrow;
+                if to.x > x { x += 1; arrow = '>'; }
+                else if to.x < x { x -= 1; arrow = '<'; }
+                else if to.y > y { y += 1; arrow = 'v'; }
+                else { y -= 1; arrow = '^'; }
+                overlay[y][x] = arrow;
+            }
+        }
+        println!("Path:\n");
+        for row in overlay {
+            println!("{}", row.iter().collect::<String>());
         }
-        println!("total cost: {:}", result.1);
-        result.1
     }
 }
 
 impl AOCProblem for Day17 {
-    fn handle_line(&mut self, line: &str, config: &Config) {
-        self.variant = config.variant;
+    fn configure(&mut self, config: &Config) -> Result<(), String> {
+        // Part a: we can move 1-3
+        // part b: we can move 4-10
+        let (min_steps, max_steps) = if config.variant { (4, 10) } else { (1, 3) };
+        self.min_steps = config.get_option("min_run")?.unwrap_or(min_steps);
+        self.max_steps = config.get_option("max_run")?.unwrap_or(max_steps);
+        if self.min_steps < 1 {
+            // Have to move at least 1 block each turn
+            return Err("min_run has to be at least 1".to_string());
+        }
+        if self.max_steps < self.min_steps {
+            return Err(format!("max_run {} is less than min_run {}", self.max_steps, self.min_steps));
+        }
+        let cell = |key: &str| -> Result<Option<(usize, usize)>, String> {
+            match config.get_option::<String>(key)? {
+                Some(c) => match parse_cell(&c) {
+                    Some(p) => Ok(Some(p)),
+                    None => Err(format!("{}={} should look like x,y", key, c)),
+                },
+                None => Ok(None),
+            }
+        };
+        self.start = cell("start")?.unwrap_or((0, 0));
+        self.goal = cell("goal")?;
+        self.compare = config.get_option("compare")?.unwrap_or(false);
+        self.algorithm = config.get_option("search")?.unwrap_or("buckets".to_string());
+        if !["astar", "dijkstra", "buckets"].contains(&self.algorithm.as_str()) {
+            return Err(format!("search has to be astar, dijkstra or buckets, not {}", self.algorithm));
+        }
+        self.bench = config.get_option("bench")?.unwrap_or(false);
+        Ok(())
+    }
+
+    fn handle_line(&mut self, line: &str, _config: &Config) {
         let c_row: Vec<char> = line.chars().collect();
         let mut row: Vec<u8> = Vec::new();
         for c in c_row {
@@ -175,7 +341,31 @@ impl AOCProblem for Day17 {
     // Just count the items in the list
     fn compute_a(&mut self) -> String {
         print_board(&self.board);
-        self.search().to_string()
+        if !self.on_board(self.start) {
+            eprintln!("start={},{} is off the board, x has to be under {} and y under {}",
+                self.start.0, self.start.1, self.board.first().map_or(0, |r| r.len()), self.board.len());
+            return "Error".to_string();
+        }
+        if let Some(goal) = self.goal.filter(|g| !self.on_board(*g)) {
+            eprintln!("goal={},{} is off the board, x has to be under {} and y under {}",
+                goal.0, goal.1, self.board.first().map_or(0, |r| r.len()), self.board.len());
+            return "Error".to_string();
+        }
+        println!("Moving {}-{} blocks from {:?} to {:?}", self.min_steps, self.max_steps,
+            self.start, (self.goal_location().x, self.goal_location().y));
+        if self.bench {
+            self.bench();
+        }
+        let (path, cost) = match self.run_search(&self.algorithm) {
+            Some(r) => r,
+            None => { eprintln!("Can't get to the goal!!"); return "Error".to_string(); },
+        };
+        println!("total cost: {:}", cost);
+        self.print_path(&path);
+        if self.compare && !self.compare_search(self.search().map_or(-1, |r| r.1)) {
+            eprintln!("A* and Dijkstra disagree!");
+        }
+        cost.to_string()
     }
 
     fn compute_b(&mut self) -> String {
@@ -1,99 +1,229 @@
 use crate::{Config, AOCProblem};
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::Write;
+
+// Biggest lagoon we'll draw, part b is far too big to rasterize
+const MAX_RASTER_CELLS: i64 = 4_000_000;
+
+// Image colors for the inside of the lagoon and the ground around it
+const LAGOON_COLOR: (u8, u8, u8) = (80, 80, 80);
+const GROUND_COLOR: (u8, u8, u8) = (0, 0, 0);
 
 #[derive(Debug)]
 struct Op {
     dir: char,
-    steps: usize,
-    // In the end, we didn't need to store this
-    // color: String,  
+    steps: i64,
+    // The trench color for this segment, r g b
+    color: (u8, u8, u8),
 }
 
 pub struct Day18 {
-    board: Vec<Vec<char>>,
-    dug: Vec<Vec<char>>,
     opcodes: Vec<Op>,
-    test: bool,
+    // Draw the lagoon after computing the area
+    visualize: bool,
+    // Write the lagoon to this PPM image, scaled so the longest side is image_size pixels
+    image: Option<String>,
+    image_size: usize,
 }
 
-fn _print_board(board: &Vec<Vec<char>>) {
+fn print_board(board: &[Vec<char>]) {
     println!("Board:\n");
-    for i in 0..board.len() {
-        let foo = &board[i].iter().collect::<String>();
-        println!("{:?}", foo);
+    for row in board {
+        println!("{}", row.iter().collect::<String>());
     }
 }
 
 impl Day18 {
-    // Let's pick a large enough board to allocate, rather than trying to dynamically size
-    
     pub fn new() -> Day18 {
         Day18 {
-            board: Vec::new(),
-            dug: Vec::new(),
             opcodes: Vec::new(),
-            test: true,
+            visualize: false,
+            image: None,
+            image_size: 1000,
         }
     }
 
-    fn init_board(&mut self, width: usize, height: usize) {
-        for _ in 0..height {
-            let row = vec!['.'; width];
-            self.board.push(row);
-            let drow = vec!['.'; width];
-            self.dug.push(drow);
+    // Corners of the trench, starting at 0,0 and ending back there
+    fn vertices(&self) -> Vec<(i64, i64)> {
+        let mut cur_x: i64 = 0;
+        let mut cur_y: i64 = 0;
+        let mut verts = vec![(cur_x, cur_y)];
+        for op in &self.opcodes {
+            match op.dir {
+                'R' => cur_x += op.steps,
+                'D' => cur_y += op.steps,
+                'L' => cur_x -= op.steps,
+                'U' => cur_y -= op.steps,
+                _ => eprintln!("Unrecognized operation {:?}", op),
+            }
+            verts.push((cur_x, cur_y));
         }
+        verts
     }
 
-    fn dig(&mut self, start_x: i64, start_y: i64) -> i64 {
-        let mut cur_x: i64 = start_x;
-        let mut cur_y = start_y;
-        let mut area = 0;
-        let mut last_x = start_x;
-        let mut last_y = start_y;
+    fn dig(&self) -> i64 {
+        let verts = self.vertices();
+        let mut area: i64 = 0;
         let mut len: i64 = 0;
-        for op in &self.opcodes {
-            println!("Digging {}{} from {},{}", op.dir, op.steps, cur_x, cur_y);
-            for _ in 1..op.steps+1 {
-                match op.dir {
-                    'R' => cur_x += 1,
-                    'D' => cur_y += 1,
-                    'L' => cur_x -= 1,
-                    'U' => cur_y -= 1,
-                    _ => eprintln!("Unrecognized operation {:?}", op),
-                }
-            }
-            
+        for pair in verts.windows(2) {
+            let ((last_x, last_y), (cur_x, cur_y)) = (pair[0], pair[1]);
             // Shoelace formula, A = 1/2 SUM (y0+y1)(x0-x1)
-            // We consider the perimeter to be inside the polygon too, so add steps
             area += (cur_x + last_x) * (cur_y - last_y);
-            len += op.steps as i64;
-            last_x = cur_x;
-            last_y = cur_y;
+            len += (cur_x - last_x).abs() + (cur_y - last_y).abs();
         }
-        len = len + 1; // Include start
-        area = area / 2;
-        // Pick's theorem
+        area = area.abs() / 2;
+        // Pick's theorem, interior = A - len/2 + 1
+        //  We consider the perimeter to be inside the polygon too, so add the len back
         area + (len / 2) + 1
     }
 
+    // Draw the trench and fill in the inside, on a board just big enough to hold it
+    //  None if it's too big to bother
+    fn rasterize(&self) -> Option<Vec<Vec<char>>> {
+        let verts = self.vertices();
+        let min_x = verts.iter().map(|v| v.0).min()?;
+        let max_x = verts.iter().map(|v| v.0).max()?;
+        let min_y = verts.iter().map(|v| v.1).min()?;
+        let max_y = verts.iter().map(|v| v.1).max()?;
+        // Leave a 1 cell border so we can flood fill the outside from the corner
+        let width = max_x - min_x + 3;
+        let height = max_y - min_y + 3;
+        if width * height > MAX_RASTER_CELLS {
+            eprintln!("Lagoon is {} x {}, too big to draw", width, height);
+            return None;
+        }
+        let mut board = vec![vec!['.'; width as usize]; height as usize];
+        for pair in verts.windows(2) {
+            let ((x0, y0), (x1, y1)) = (pair[0], pair[1]);
+            for x in x0.min(x1)..=x0.max(x1) {
+                for y in y0.min(y1)..=y0.max(y1) {
+                    board[(y - min_y + 1) as usize][(x - min_x + 1) as usize] = '#';
+                }
+            }
+        }
+
+        // Anything we can't reach from the outside is inside the lagoon
+        let mut outside = vec![vec![false; width as usize]; height as usize];
+        let mut work: VecDeque<(usize, usize)> = VecDeque::new();
+        work.push_back((0, 0));
+        outside[0][0] = true;
+        while let Some((x, y)) = work.pop_front() {
+            let neighbors = [(x.wrapping_sub(1), y), (x + 1, y), (x, y.wrapping_sub(1)), (x, y + 1)];
+            for (nx, ny) in neighbors {
+                if ny < board.len() && nx < board[ny].len() && !outside[ny][nx] && board[ny][nx] != '#' {
+                    outside[ny][nx] = true;
+                    work.push_back((nx, ny));
+                }
+            }
+        }
+        for (y, row) in board.iter_mut().enumerate() {
+            for (x, cell) in row.iter_mut().enumerate() {
+                if !outside[y][x] { *cell = '#'; }
+            }
+        }
+        Some(board)
+    }
+
+    // Write the lagoon as a binary PPM image, trench segments in their own colors and the inside filled
+    //  Coordinates are scaled so the longest side is size pixels, part b is millions of cubes across
+    //  Cube x covers pixels floor(x * scale) up to floor((x + 1) * scale), but always at least 1 pixel
+    fn write_image(&self, filename: &str, size: usize) -> std::io::Result<()> {
+        let verts = self.vertices();
+        let min_x = verts.iter().map(|v| v.0).min().unwrap_or(0);
+        let max_x = verts.iter().map(|v| v.0).max().unwrap_or(0);
+        let min_y = verts.iter().map(|v| v.1).min().unwrap_or(0);
+        let max_y = verts.iter().map(|v| v.1).max().unwrap_or(0);
+        let extent = (max_x - min_x).max(max_y - min_y) + 1;
+        let scale = size as f64 / extent as f64;
+        let to_pixel = |c: i64| (c as f64 * scale).floor() as usize;
+
+        // 1 pixel border so we can flood fill the outside from the corner
+        let width = to_pixel(max_x - min_x + 1) + 2;
+        let height = to_pixel(max_y - min_y + 1) + 2;
+        let mut pixels: Vec<Option<(u8, u8, u8)>> = vec![None; width * height];
+        for (pair, op) in verts.windows(2).zip(&self.opcodes) {
+            let ((x0, y0), (x1, y1)) = (pair[0], pair[1]);
+            let px0 = to_pixel(x0.min(x1) - min_x) + 1;
+            let px1 = (to_pixel(x0.max(x1) - min_x + 1) + 1).max(px0 + 1);
+            let py0 = to_pixel(y0.min(y1) - min_y) + 1;
+            let py1 = (to_pixel(y0.max(y1) - min_y + 1) + 1).max(py0 + 1);
+            for py in py0..py1.min(height) {
+                for px in px0..px1.min(width) {
+                    pixels[py * width + px] = Some(op.color);
+                }
+            }
+        }
+
+        let mut outside = vec![false; width * height];
+        let mut work: VecDeque<usize> = VecDeque::new();
+        work.push_back(0);
+        outside[0] = true;
+        while let Some(p) = work.pop_front() {
+            let (x, y) = (p % width, p / width);
+            let mut neighbors: Vec<usize> = Vec::new();
+            if x > 0 { neighbors.push(p - 1); }
+            if x + 1 < width { neighbors.push(p + 1); }
+            if y > 0 { neighbors.push(p - width); }
+            if y + 1 < height { neighbors.push(p + width); }
+            for n in neighbors {
+                if !outside[n] && pixels[n].is_none() {
+                    outside[n] = true;
+                    work.push_back(n);
+                }
+            }
+        }
+
+        let mut ofile = File::create(filename)?;
+        write!(ofile, "P6\n{} {}\n255\n", width, height)?;
+        let mut data: Vec<u8> = Vec::with_capacity(width * height * 3);
+        for (p, pixel) in pixels.iter().enumerate() {
+            let (r, g, b) = match pixel {
+                Some(color) => *color,
+                None if outside[p] => GROUND_COLOR,
+                None => LAGOON_COLOR,
+            };
+            data.extend([r, g, b]);
+        }
+        ofile.write_all(&data)?;
+        // Part b is shrunk by millions, so say how many cubes each pixel is instead
+        let scale_str = if scale >= 1.0 {
+            format!("{:.2} pixels per cube", scale)
+        } else {
+            format!("{:.0} cubes per pixel", 1.0 / scale)
+        };
+        println!("Wrote {} x {} image to {}, {}", width, height, filename, scale_str);
+        Ok(())
+    }
 }
 
 impl AOCProblem for Day18 {
+    fn configure(&mut self, config: &Config) -> Result<(), String> {
+        self.visualize = config.get_option("visualize")?.unwrap_or(false);
+        self.image = config.get_option("image")?;
+        self.image_size = config.get_option("image_size")?.unwrap_or(1000);
+        if self.image_size < 1 {
+            return Err("image_size has to be at least 1".to_string());
+        }
+        Ok(())
+    }
+
     fn handle_line(&mut self, line: &str, config: &Config) {
-        //let line_str: String = String::from(line);
         let mut line_iter = line.split_whitespace();
 
         let mut dir = line_iter.next().unwrap().chars().next().unwrap();
-        let steps = line_iter.next().unwrap().parse::<usize>().unwrap();
+        let steps = line_iter.next().unwrap().parse::<i64>().unwrap();
         let mut color = String::from(line_iter.next().unwrap());
-        color.retain(|c| !"()#".contains(c));        
+        color.retain(|c| !"()#".contains(c));
+        let rgb = u32::from_str_radix(&color, 16).unwrap();
+        let trench_color = ((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8);
 
         if !config.variant {
-            let op = Op {dir, steps};
+            let op = Op {dir, steps, color: trench_color};
             self.opcodes.push(op);
         } else {
             // Variant.  Convert color hex digits to the real number
-            let hex_num = i32::from_str_radix(&color[0..5], 16).unwrap();
+            let hex_num = i64::from_str_radix(&color[0..5], 16).unwrap();
             match &color[5..6] {
                 "0" => dir = 'R',
                 "1" => dir = 'D',
@@ -101,22 +231,24 @@ impl AOCProblem for Day18 {
                 "3" => dir = 'U',
                 _ => eprintln!("Unable to parse hex {}", color),
             }
-            let op = Op {dir, steps: hex_num as usize};
+            let op = Op {dir, steps: hex_num, color: trench_color};
             self.opcodes.push(op);
         }
-        if config.test_input { self.test = true };
     }
-    
-    // Just count the items in the list
+
     fn compute_a(&mut self) -> String {
-        let poly_interior: i64;
-        if self.test {
-            self.init_board(10, 10);
-            poly_interior = self.dig(0, 0);
-        } 
-        else {
-            self.init_board(490, 450);
-            poly_interior = self.dig(100, 300);
+        let poly_interior = self.dig();
+        if self.visualize {
+            if let Some(board) = self.rasterize() {
+                print_board(&board);
+                let drawn = board.iter().flatten().filter(|c| **c == '#').count();
+                println!("Drawn lagoon has {} cubes", drawn);
+            }
+        }
+        if let Some(filename) = &self.image {
+            if let Err(e) = self.write_image(filename, self.image_size) {
+                eprintln!("Unable to write {}: {}", filename, e);
+            }
         }
         poly_interior.to_string()
     }
@@ -1,54 +1,174 @@
 use crate::{Config, AOCProblem};
 use std::collections::HashMap;
-use std::collections::VecDeque;
+use std::collections::HashSet;
+use std::fmt;
 
+// Ratings go from 1 to 4000 unless the domain options say otherwise
+const MIN_RATING: i64 = 1;
+const MAX_RATING: i64 = 4000;
+
+// Ratings by attribute name, in the order they were listed
 #[derive(Debug)]
 struct Part {
-    x: i64,
-    m: i64,
-    a: i64,
-    s: i64,
+    ratings: Vec<(String, i64)>,
 }
 
 impl Part {
+    // Parse a part like {x=787,m=2655,a=1222,s=2876}
+    fn parse(line: &str) -> Result<Part, String> {
+        let inner = match line.strip_prefix('{').and_then(|l| l.strip_suffix('}')) {
+            Some(i) => i,
+            None => return Err("Part has to be wrapped in {}".to_string()),
+        };
+        let mut part = Part { ratings: Vec::new() };
+        for token in inner.split(',') {
+            let (name, val) = match token.split_once('=') {
+                Some(t) => t,
+                None => return Err(format!("Missing = in {}", token)),
+            };
+            if !is_attribute_name(name) {
+                return Err(format!("Bad attribute name {}", name));
+            }
+            if part.rating(name).is_some() {
+                return Err(format!("Attribute {} is listed twice", name));
+            }
+            match val.parse::<i64>() {
+                Ok(v) => part.ratings.push((name.to_string(), v)),
+                Err(e) => return Err(format!("Rating {}: {}", val, e)),
+            }
+        }
+        Ok(part)
+    }
+
+    fn rating(&self, name: &str) -> Option<i64> {
+        self.ratings.iter().find(|(n, _)| n == name).map(|(_, v)| *v)
+    }
+
     fn score(&self) -> i64 {
-        return self.x + self.m + self.a + self.s;
+        self.ratings.iter().map(|(_, v)| v).sum()
     }
 }
 
+fn is_attribute_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+// Parse a range like 1-4000, the low end can be negative
+fn parse_range(s: &str) -> Option<(i64, i64)> {
+    let idx = s.get(1..)?.find('-')? + 1;
+    let lo = s[..idx].parse().ok()?;
+    let hi = s[idx + 1..].parse().ok()?;
+    Some((lo, hi))
+}
+
+// Parse ranges for named attributes like x:1-4000,m:1-10
+fn parse_named_ranges(s: &str) -> Option<HashMap<String, (i64, i64)>> {
+    let mut ranges = HashMap::new();
+    for token in s.split(',') {
+        let (name, range) = token.split_once(':')?;
+        ranges.insert(name.to_string(), parse_range(range)?);
+    }
+    Some(ranges)
+}
+
 #[derive(Debug, Copy, Clone)]
+enum CompareOp {
+    Less,
+    LessEq,
+    Greater,
+    GreaterEq,
+    Equal,
+}
+
+#[derive(Debug, Clone)]
 struct Condition {
-    op: char,
-    variable: char,
+    op: CompareOp,
+    variable: String,
     value: i64,
 }
 
 impl Condition {
-    fn negate(&self) -> Condition {
-        let neg_op: char;
-        let neg_val: i64;
+    // Parse a condition like a<2006, m>=10 or s==5, longer operators are checked first
+    fn parse(s: &str) -> Result<Condition, String> {
+        let idx = match s.find(['<', '>', '=']) {
+            Some(i) => i,
+            None => return Err(format!("No comparison in {}", s)),
+        };
+        let variable = &s[..idx];
+        if !is_attribute_name(variable) {
+            return Err(format!("Bad attribute name {} in {}", variable, s));
+        }
+        let ops = [(">=", CompareOp::GreaterEq), ("<=", CompareOp::LessEq), ("==", CompareOp::Equal),
+                   (">", CompareOp::Greater), ("<", CompareOp::Less)];
+        for (text, op) in ops {
+            if let Some(val) = s[idx..].strip_prefix(text) {
+                return match val.parse::<i64>() {
+                    Ok(value) => Ok(Condition { op, variable: variable.to_string(), value }),
+                    Err(e) => Err(format!("Value {} in {}: {}", val, s, e)),
+                };
+            }
+        }
+        Err(format!("Unrecognized operator in {}", s))
+    }
+
+    // The ratings that pass, inclusive
+    fn pass_range(&self) -> (i64, i64) {
         match self.op {
-            '>' => {neg_op = '<'; neg_val = self.value + 1; },
-            '<' => {neg_op = '>'; neg_val = self.value - 1; },
-            _ => panic!("Can't negate a {}", self.op),
+            CompareOp::Less => (i64::MIN, self.value - 1),
+            CompareOp::LessEq => (i64::MIN, self.value),
+            CompareOp::Greater => (self.value + 1, i64::MAX),
+            CompareOp::GreaterEq => (self.value, i64::MAX),
+            CompareOp::Equal => (self.value, self.value),
         }
-        Condition{op: neg_op, value: neg_val, variable: self.variable}
     }
 
-    fn test(&self, part: &Part) -> bool {    
-        let cur_val: i64;
-        match self.variable {
-            'x' => cur_val = part.x,
-            'm' => cur_val = part.m,
-            'a' => cur_val = part.a,
-            's' => cur_val = part.s,
-            _ => panic!("Error: unrecognized variable in condition: {:?}", self),
+    // Parts are checked for every attribute the workflows use before we get here
+    fn test(&self, part: &Part) -> bool {
+        let cur_val = match part.rating(&self.variable) {
+            Some(v) => v,
+            None => panic!("Error: part {:?} has no {}", part, self.variable),
+        };
+        let (lo, hi) = self.pass_range();
+        lo <= cur_val && cur_val <= hi
+    }
+}
+
+// Every part with ratings inside these ranges, inclusive, one range per attribute
+//  Attributes are in the order of Day19's attributes list
+#[derive(Debug, Clone)]
+struct PartBox {
+    lo: Vec<i64>,
+    hi: Vec<i64>,
+}
+
+impl PartBox {
+    fn volume(&self) -> i128 {
+        self.lo.iter().zip(&self.hi).map(|(lo, hi)| (*hi as i128 - *lo as i128 + 1).max(0)).product()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.lo.iter().zip(&self.hi).any(|(lo, hi)| lo > hi)
+    }
+
+    fn clamp(&self, i: usize, lo: i64, hi: i64) -> Option<PartBox> {
+        let mut b = self.clone();
+        b.lo[i] = b.lo[i].max(lo);
+        b.hi[i] = b.hi[i].min(hi);
+        if b.is_empty() { None } else { Some(b) }
+    }
+
+    // Cut the box into the part that passes the condition on attribute i, and the pieces that don't
+    //  == can fail on both sides, so there can be 2 of those
+    fn split(&self, i: usize, cond: &Condition) -> (Option<PartBox>, Vec<PartBox>) {
+        let (lo, hi) = cond.pass_range();
+        let mut fail = Vec::new();
+        if lo > i64::MIN {
+            fail.extend(self.clamp(i, i64::MIN, lo - 1));
         }
-        match self.op {
-            '>' => if cur_val > self.value { return true; } else { return false; },
-            '<' => if cur_val < self.value { return true; } else { return false; },
-            _ => panic!("Error: unrecognized operator in condition: {:?}", self),
+        if hi < i64::MAX {
+            fail.extend(self.clamp(i, hi + 1, i64::MAX));
         }
+        (self.clamp(i, lo, hi), fail)
     }
 }
 
@@ -63,7 +183,7 @@ impl Rule {
         match &self.cond {
             None => Some(&self.if_true),
             Some(c) => {
-                if c.test(&part) { return Some(&self.if_true) } else { return None };
+                if c.test(part) { return Some(&self.if_true) } else { return None };
             }
         }
     }
@@ -88,10 +208,177 @@ impl Workflow {
     }
 }
 
+// Where a decision goes next, a final answer or another decision
+//  Rejects remember which workflow sent the parts to R, as an index into DecisionTree's labels
+#[derive(Debug, Clone, Copy)]
+enum Target {
+    Accept,
+    Reject(usize),
+    Node(usize),
+}
+
+// One conditional rule, the unconditional last rule of a workflow just becomes the if_false target
+#[derive(Debug)]
+struct DecisionNode {
+    // Index of cond's attribute in the box
+    attribute: usize,
+    cond: Condition,
+    if_true: Target,
+    if_false: Target,
+}
+
+// The workflows compiled down to binary decisions, starting from in
+//  Workflows used by more than one rule are only compiled once, so this is really a DAG
+#[derive(Debug)]
+struct DecisionTree {
+    nodes: Vec<DecisionNode>,
+    root: Target,
+    labels: Vec<String>,
+}
+
+impl DecisionTree {
+    fn compile(workflows: &HashMap<String, Workflow>, attributes: &[String]) -> Result<DecisionTree, String> {
+        DecisionTree::compile_from("in", workflows, attributes)
+    }
+
+    // Compile starting from any workflow, not just in
+    fn compile_from(label: &str, workflows: &HashMap<String, Workflow>, attributes: &[String]) -> Result<DecisionTree, String> {
+        let mut tree = DecisionTree { nodes: Vec::new(), root: Target::Accept, labels: Vec::new() };
+        let mut compiled: HashMap<String, Target> = HashMap::new();
+        let mut in_progress: Vec<String> = Vec::new();
+        tree.root = tree.compile_label(label, workflows, attributes, &mut compiled, &mut in_progress)?;
+        Ok(tree)
+    }
+
+    fn compile_label(&mut self, label: &str, workflows: &HashMap<String, Workflow>, attributes: &[String],
+                     compiled: &mut HashMap<String, Target>, in_progress: &mut Vec<String>) -> Result<Target, String> {
+        match label {
+            "A" => return Ok(Target::Accept),
+            "R" => return Ok(Target::Reject(self.label_index(label))),
+            _ => (),
+        }
+        if let Some(t) = compiled.get(label) {
+            return Ok(*t);
+        }
+        if in_progress.iter().any(|l| l == label) {
+            return Err(format!("Workflow {} loops back on itself: {} -> {}", label, in_progress.join(" -> "), label));
+        }
+        let workflow = match workflows.get(label) {
+            Some(w) => w,
+            None => return Err(format!("Workflow {} is not defined", label)),
+        };
+        in_progress.push(label.to_string());
+
+        // Build from the last rule back, each rule falls through to the one after it
+        let mut next: Option<Target> = None;
+        for rule in workflow.rules.iter().rev() {
+            // This workflow is the one rejecting, even if it's the only thing it does
+            let if_true = match rule.if_true.as_str() {
+                "R" => Target::Reject(self.label_index(label)),
+                _ => self.compile_label(&rule.if_true, workflows, attributes, compiled, in_progress)?,
+            };
+            next = match (&rule.cond, next) {
+                (None, _) => Some(if_true),
+                (Some(_), None) => return Err(format!("Workflow {} doesn't end with a rule for everything else", label)),
+                (Some(cond), Some(if_false)) => {
+                    let attribute = match attributes.iter().position(|a| *a == cond.variable) {
+                        Some(a) => a,
+                        None => return Err(format!("Workflow {} uses unknown attribute {}", label, cond.variable)),
+                    };
+                    self.nodes.push(DecisionNode { attribute, cond: cond.clone(), if_true, if_false });
+                    Some(Target::Node(self.nodes.len() - 1))
+                },
+            };
+        }
+        in_progress.pop();
+        let target = match next {
+            Some(t) => t,
+            None => return Err(format!("Workflow {} has no rules", label)),
+        };
+        compiled.insert(label.to_string(), target);
+        Ok(target)
+    }
+
+    fn label_index(&mut self, label: &str) -> usize {
+        match self.labels.iter().position(|l| l == label) {
+            Some(i) => i,
+            None => {
+                self.labels.push(label.to_string());
+                self.labels.len() - 1
+            },
+        }
+    }
+
+    // Split a box into the pieces that get accepted, and the pieces that get rejected along with
+    //  the workflow that rejected them
+    fn partition(&self, part_box: PartBox) -> (Vec<PartBox>, Vec<(String, PartBox)>) {
+        let mut accepted: Vec<PartBox> = Vec::new();
+        let mut rejected: Vec<(String, PartBox)> = Vec::new();
+        let mut work: Vec<(Target, PartBox)> = vec![(self.root, part_box)];
+        while let Some((target, cur_box)) = work.pop() {
+            match target {
+                Target::Accept => accepted.push(cur_box),
+                Target::Reject(l) => rejected.push((self.labels[l].clone(), cur_box)),
+                Target::Node(i) => {
+                    let node = &self.nodes[i];
+                    let (pass, fail) = cur_box.split(node.attribute, &node.cond);
+                    if let Some(b) = pass { work.push((node.if_true, b)); }
+                    for b in fail { work.push((node.if_false, b)); }
+                },
+            }
+        }
+        (accepted, rejected)
+    }
+}
+
+// Problems found by Day19::analyze
+//  The fatal ones would make part a loop forever or panic, the rest are just suspicious
+#[derive(Debug)]
+enum Warning {
+    MissingStart,
+    ParseError(String),
+    Undefined { workflow: String, target: String },
+    NoFallback { workflow: String },
+    Cycle(Vec<String>),
+    Unreachable { workflow: String },
+    DeadRule { workflow: String, rule: usize },
+    SameOutcome { workflow: String, accept: bool },
+}
+
+impl Warning {
+    fn is_fatal(&self) -> bool {
+        matches!(self, Warning::MissingStart | Warning::ParseError(_) | Warning::Undefined { .. } | Warning::NoFallback { .. } | Warning::Cycle(_))
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Warning::MissingStart => write!(f, "There is no in workflow to start from"),
+            Warning::ParseError(e) => write!(f, "{}", e),
+            Warning::Undefined { workflow, target } => write!(f, "Workflow {} sends parts to undefined workflow {}", workflow, target),
+            Warning::NoFallback { workflow } => write!(f, "Workflow {} doesn't end with a rule for everything else", workflow),
+            Warning::Cycle(path) => write!(f, "Workflows loop: {}", path.join(" -> ")),
+            Warning::Unreachable { workflow } => write!(f, "Workflow {} can't be reached from in", workflow),
+            Warning::DeadRule { workflow, rule } => write!(f, "Workflow {} rule {} can never fire, no parts are left for it", workflow, rule + 1),
+            Warning::SameOutcome { workflow, accept } => write!(f, "Workflow {} always {}", workflow, if *accept { "accepts" } else { "rejects" }),
+        }
+    }
+}
+
 pub struct Day19 {
     workflows: HashMap<String, Workflow>,
     parts: Vec<Part>,
     parsing_parts: bool,
+    // Lines we couldn't make sense of, running without them would change the answer
+    parse_errors: Vec<String>,
+    // Every attribute named by a workflow or a part, in the order we first saw them
+    attributes: Vec<String>,
+    // Ratings range for part b, domain for every attribute unless domains has its own
+    domain: (i64, i64),
+    domains: HashMap<String, (i64, i64)>,
+    // Only count parts inside these ranges for part b
+    box_ranges: HashMap<String, (i64, i64)>,
 }
 
 impl Day19 {
@@ -100,7 +387,163 @@ impl Day19 {
             workflows: HashMap::new(),
             parts: Vec::new(),
             parsing_parts: false,
+            parse_errors: Vec::new(),
+            attributes: Vec::new(),
+            domain: (MIN_RATING, MAX_RATING),
+            domains: HashMap::new(),
+            box_ranges: HashMap::new(),
+        }
+    }
+
+    fn add_attribute(&mut self, name: &str) {
+        if !self.attributes.iter().any(|a| a == name) {
+            self.attributes.push(name.to_string());
+        }
+    }
+
+    // Every possible part, from the domain options
+    fn domain_box(&self) -> PartBox {
+        let mut part_box = PartBox { lo: Vec::new(), hi: Vec::new() };
+        for attr in &self.attributes {
+            let (lo, hi) = *self.domains.get(attr).unwrap_or(&self.domain);
+            part_box.lo.push(lo);
+            part_box.hi.push(hi);
+        }
+        part_box
+    }
+
+    // The box of every part we count in part b
+    fn part_box(&self) -> PartBox {
+        let mut part_box = PartBox { lo: Vec::new(), hi: Vec::new() };
+        for attr in &self.attributes {
+            let (mut lo, mut hi) = *self.domains.get(attr).unwrap_or(&self.domain);
+            if let Some((box_lo, box_hi)) = self.box_ranges.get(attr) {
+                lo = lo.max(*box_lo);
+                hi = hi.min(*box_hi);
+            }
+            part_box.lo.push(lo);
+            part_box.hi.push(hi);
+        }
+        part_box
+    }
+
+    // Look over the workflows for anything broken or pointless, without running any parts
+    fn analyze(&self) -> Vec<Warning> {
+        let mut warnings: Vec<Warning> = Vec::new();
+        let mut labels: Vec<&String> = self.workflows.keys().collect();
+        labels.sort();
+
+        if !self.workflows.contains_key("in") {
+            warnings.push(Warning::MissingStart);
+        }
+        for e in &self.parse_errors {
+            warnings.push(Warning::ParseError(e.clone()));
+        }
+        for label in &labels {
+            let workflow = &self.workflows[*label];
+            for rule in &workflow.rules {
+                if rule.if_true != "A" && rule.if_true != "R" && !self.workflows.contains_key(&rule.if_true) {
+                    warnings.push(Warning::Undefined { workflow: label.to_string(), target: rule.if_true.clone() });
+                }
+            }
+            if workflow.rules.last().is_none_or(|r| r.cond.is_some()) {
+                warnings.push(Warning::NoFallback { workflow: label.to_string() });
+            }
+        }
+
+        // Depth first from every workflow, a workflow we're still inside of means a loop
+        let mut done: HashSet<&str> = HashSet::new();
+        for label in &labels {
+            let mut path: Vec<&str> = Vec::new();
+            self.find_cycles(label, &mut path, &mut done, &mut warnings);
+        }
+
+        let mut reached: HashSet<&str> = HashSet::new();
+        let mut work: Vec<&str> = vec!["in"];
+        while let Some(label) = work.pop() {
+            if let Some(workflow) = self.workflows.get(label) {
+                if reached.insert(label) {
+                    work.extend(workflow.rules.iter().map(|r| r.if_true.as_str()));
+                }
+            }
+        }
+        for label in &labels {
+            if !reached.contains(label.as_str()) {
+                warnings.push(Warning::Unreachable { workflow: label.to_string() });
+            }
         }
+
+        // A rule is dead if nothing is left for it after the rules before it, whatever reaches the workflow
+        let domain = self.domain_box();
+        for label in &labels {
+            let mut remaining: Vec<PartBox> = vec![domain.clone()];
+            for (idx, rule) in self.workflows[*label].rules.iter().enumerate() {
+                let mut fired = false;
+                match &rule.cond {
+                    None => {
+                        fired = !remaining.is_empty();
+                        remaining.clear();
+                    },
+                    Some(cond) => {
+                        let attr = self.attributes.iter().position(|a| *a == cond.variable).unwrap();
+                        let mut next: Vec<PartBox> = Vec::new();
+                        for b in &remaining {
+                            let (pass, fail) = b.split(attr, cond);
+                            fired |= pass.is_some();
+                            next.extend(fail);
+                        }
+                        remaining = next;
+                    },
+                }
+                if !fired {
+                    warnings.push(Warning::DeadRule { workflow: label.to_string(), rule: idx });
+                }
+            }
+        }
+
+        // Workflows that end up in the same place for every part could just be A or R
+        for label in &labels {
+            let tree = match DecisionTree::compile_from(label, &self.workflows, &self.attributes) {
+                Ok(t) => t,
+                Err(_) => continue,
+            };
+            let (accepted, rejected) = tree.partition(domain.clone());
+            if rejected.is_empty() || accepted.is_empty() {
+                warnings.push(Warning::SameOutcome { workflow: label.to_string(), accept: rejected.is_empty() });
+            }
+        }
+        warnings
+    }
+
+    fn find_cycles<'a>(&'a self, label: &'a str, path: &mut Vec<&'a str>, done: &mut HashSet<&'a str>, warnings: &mut Vec<Warning>) {
+        if let Some(start) = path.iter().position(|l| *l == label) {
+            let mut cycle: Vec<String> = path[start..].iter().map(|l| l.to_string()).collect();
+            cycle.push(label.to_string());
+            warnings.push(Warning::Cycle(cycle));
+            return;
+        }
+        let workflow = match self.workflows.get(label) {
+            Some(w) => w,
+            None => return,
+        };
+        if done.contains(label) {
+            return;
+        }
+        path.push(label);
+        for rule in &workflow.rules {
+            self.find_cycles(&rule.if_true, path, done, warnings);
+        }
+        path.pop();
+        done.insert(label);
+    }
+
+    // Print the warnings, false if any of them mean we can't run the workflows
+    fn check_workflows(&self) -> bool {
+        let warnings = self.analyze();
+        for w in &warnings {
+            println!("Warning: {}", w);
+        }
+        !warnings.iter().any(|w| w.is_fatal())
     }
 
     fn test_part(&self, part: &Part) -> bool {
@@ -119,66 +562,41 @@ impl Day19 {
     }
 }
 
-#[derive(Debug, Clone)]
-struct WorkItem {
-    cond_list: Vec<Condition>,
-    current_workflow: String,
-    current_rule: usize,
-}
-
-impl WorkItem {
-    // How many options for variable 'var' pass the conditions in the list?
-    fn count_options_char(&self, var: char) -> i64 {
-        // Get all conditions that apply to this variable
-        // Get the largest max condition and the lowest min condition
-        let mut min = 1;
-        let mut max = 4000;
-
-        for cond in &self.cond_list {
-            if cond.variable == var {
-                match cond.op {
-                    '<' => if max > cond.value { max = cond.value - 1; },
-                    '>' => if min < cond.value { min = cond.value + 1; },
-                    _ => (),
-                }
+impl AOCProblem for Day19 {
+    fn configure(&mut self, config: &Config) -> Result<(), String> {
+        if let Some(d) = config.get_option::<String>("domain")? {
+            match parse_range(&d) {
+                Some(r) => self.domain = r,
+                None => return Err(format!("Unable to parse domain {}, should look like 1-4000", d)),
             }
         }
-        if max <= min { return 0; }
-        (max - min) + 1
+        if let Some(d) = config.get_option::<String>("domains")? {
+            match parse_named_ranges(&d) {
+                Some(r) => self.domains = r,
+                None => return Err(format!("Unable to parse domains {}, should look like x:1-4000,m:1-10", d)),
+            }
+        }
+        if let Some(b) = config.get_option::<String>("box")? {
+            match parse_named_ranges(&b) {
+                Some(r) => self.box_ranges = r,
+                None => return Err(format!("Unable to parse box {}, should look like x:1-1000,s:1-10", b)),
+            }
+        }
+        Ok(())
     }
 
-    fn count_options(&self) -> i64 {
-        let opt_x = self.count_options_char('x');
-        let opt_m = self.count_options_char('m');
-        let opt_a = self.count_options_char('a');
-        let opt_s = self.count_options_char('s');
-        let count = opt_x * opt_m * opt_a * opt_s;
-        println!("Score: x {} m {} a {} s {} = {}", opt_x, opt_m, opt_a, opt_s, count);
-        count
-
-    }
-}
-
-impl AOCProblem for Day19 {
     fn handle_line(&mut self, line: &str, _config: &Config) {
         if self.parsing_parts {
-            let line_str = &line[1..line.len()-1];  // Strip {}
-            let line_split = line_str.split(",");
-            let mut part = Part {x: 0, m: 0, a: 0, s: 0};
-            for token in line_split {
-                let mut t_split = token.split("=");
-                let var = t_split.next().unwrap();
-                let val = t_split.next().unwrap().parse::<i64>().unwrap();
-                match var {
-                    "x" => part.x = val,
-                    "m" => part.m = val,
-                    "a" => part.a = val,
-                    "s" => part.s = val,
-                    _ => panic!("Can't parse part: {}", token),
-                }
+            match Part::parse(line) {
+                Ok(part) => {
+                    for (name, _) in &part.ratings {
+                        self.add_attribute(name);
+                    }
+                    println!("Adding part: {:?}", &part);
+                    self.parts.push(part);
+                },
+                Err(e) => self.parse_errors.push(format!("Unable to parse part {}: {}", line, e)),
             }
-            println!("Adding part: {:?}", &part);
-            self.parts.push(part);
 
         } else if line.len() == 0 {
             self.parsing_parts = true;
@@ -190,19 +608,19 @@ impl AOCProblem for Day19 {
             let workflow_iter = workflow_str.split(",");
             let mut rule_vec = Vec::new();
             for token in workflow_iter {
-                let mut token_split = token.split(":");
-                let cond_str = token_split.next().unwrap();
-                let ift = token_split.next();
-                let rule = match ift {
-                    None => Rule{cond: None, if_true: String::from(cond_str)},
-                    Some(lbl) => {
-                        let mut cond_split = cond_str.split(|c| c == '>' || c == '<');
-                        let variable = cond_split.next().unwrap().chars().next().unwrap();
-                        let value = cond_split.next().unwrap().parse::<i64>().unwrap();
-                        let mut op = '>';
-                        if cond_str.contains("<") { op = '<'};
-                        let cond = Condition {op, variable, value};
-                        Rule{cond: Some(cond), if_true: lbl.to_string()}
+                let rule = match token.split_once(':') {
+                    None => Rule{cond: None, if_true: String::from(token)},
+                    Some((cond_str, lbl)) => {
+                        match Condition::parse(cond_str) {
+                            Ok(cond) => {
+                                self.add_attribute(&cond.variable);
+                                Rule{cond: Some(cond), if_true: lbl.to_string()}
+                            },
+                            Err(e) => {
+                                self.parse_errors.push(format!("Unable to parse rule {} in workflow {}: {}", token, label, e));
+                                continue;
+                            },
+                        }
                     }
                 };
                 rule_vec.push(rule);
@@ -215,9 +633,23 @@ impl AOCProblem for Day19 {
     
     // Just count the items in the list
     fn compute_a(&mut self) -> String {
+        if !self.check_workflows() {
+            return "Error".to_string();
+        }
+        // Every part needs a rating for everything the workflows look at
+        let used: Vec<&String> = self.workflows.values()
+            .flat_map(|w| w.rules.iter().filter_map(|r| r.cond.as_ref().map(|c| &c.variable)))
+            .collect();
+        for part in &self.parts {
+            if let Some(missing) = used.iter().find(|v| part.rating(v).is_none()) {
+                eprintln!("Part {:?} has no {} rating", part, missing);
+                return "Error".to_string();
+            }
+        }
+
         let mut score = 0;
         for part in &self.parts {
-            if self.test_part(&part) {
+            if self.test_part(part) {
                 let part_score = part.score();
                 println!("Accepted part: {:?} Score: {}", part, part_score);
                 score += part_score;
@@ -227,63 +659,38 @@ impl AOCProblem for Day19 {
     }
 
     fn compute_b(&mut self) -> String {
-        let mut work_queue: VecDeque<WorkItem> = VecDeque::new();
-        let empty_cond: Vec<Condition> = Vec::new();
-        let mut accept_paths: Vec<WorkItem> = Vec::new();
-        let start = WorkItem {cond_list: empty_cond, current_workflow: "in".to_string(), current_rule: 0};
-        work_queue.push_front(start);
-        while let Some(next) = work_queue.pop_front().to_owned() {
-            println!("Handling {:?}", next);
-            if "R".eq(&next.current_workflow) {
-                continue;
-            }
-            if "A".eq(&next.current_workflow) {
-                accept_paths.push(next.clone());
-                continue;
-            }
-            let workflow = self.workflows.get(&next.current_workflow).unwrap();
-            let rule = &workflow.rules[next.current_rule];
-            match rule.cond {
-                None => {
-                    if rule.if_true.eq("R") {
-                        // Done with this flow, rejected
-                        continue;
-                    } else if rule.if_true.eq("A") {
-                        accept_paths.push(next.clone());
-                        continue;
-                    } else {
-                        let mut work_item_false = next.clone();
-                        work_item_false.current_rule = 0;
-                        work_item_false.current_workflow = rule.if_true.clone();
-                        work_queue.push_front(work_item_false);
-                    }
-                },
-                Some(c) => {
-                    // For each rule, there are 2 paths to try, it's true or false.  Push both on
-                    let mut cond_vec_f = next.cond_list.clone();
-                    let neg_cond = c.negate();
-                    cond_vec_f.push(neg_cond);
-                    let work_item_false = WorkItem{cond_list: cond_vec_f, current_workflow: next.current_workflow, current_rule: next.current_rule + 1};
-                    work_queue.push_front(work_item_false);
-
-                    let mut cond_vec1 = next.cond_list.clone();
-                    cond_vec1.push(c);
-                    let work_item_true = WorkItem{cond_list: cond_vec1, current_workflow: rule.if_true.clone(), current_rule: 0};
-                    work_queue.push_front(work_item_true);
-                            
-                }
-            }
-            println!("WorkQueue size: {}", work_queue.len());            
+        if !self.check_workflows() {
+            return "Error".to_string();
         }
+        let tree = match DecisionTree::compile(&self.workflows, &self.attributes) {
+            Ok(t) => t,
+            Err(e) => {
+                eprintln!("Unable to compile workflows: {}", e);
+                return "Error".to_string();
+            },
+        };
+        println!("Compiled {} workflows into {} decisions", self.workflows.len(), tree.nodes.len());
 
-        let mut count: i64 = 0;
-        for accept_path in accept_paths {
-            println!("Accept Path: {:?}", accept_path);
-            let combos = accept_path.count_options();
+        let part_box = self.part_box();
+        let ranges: Vec<String> = self.attributes.iter().enumerate()
+            .map(|(i, a)| format!("{}:{}-{}", a, part_box.lo[i], part_box.hi[i]))
+            .collect();
+        let (accepted, rejected) = tree.partition(part_box);
+        let count: i128 = accepted.iter().map(|b| b.volume()).sum();
+        println!("Box {}: {} accepted boxes, {} rejected boxes", ranges.join(","), accepted.len(), rejected.len());
 
-            println!("Combos: {}", combos);
-            count += combos;
+        let mut rejected_by: HashMap<String, i128> = HashMap::new();
+        for (workflow, b) in &rejected {
+            *rejected_by.entry(workflow.clone()).or_insert(0) += b.volume();
+        }
+        let mut rejected_by: Vec<(String, i128)> = rejected_by.into_iter().collect();
+        rejected_by.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        for (workflow, volume) in rejected_by.iter().take(5) {
+            println!("Workflow {} rejects {} parts", workflow, volume);
+        }
+        if let Some((workflow, volume)) = rejected_by.first() {
+            println!("Workflow {} rejects the most, {} parts", workflow, volume);
         }
-        return count.to_string();
+        count.to_string()
     }
 }
@@ -1,5 +1,8 @@
 use crate::{Config, AOCProblem};
 use std::{collections::HashMap, collections::hash_map::Entry};
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
 
 #[derive(Debug)]
 struct Pulse {
@@ -16,6 +19,76 @@ enum ModuleType {
     Output,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Direction {
+    Sends,
+    Gets,
+}
+
+// Something to look for while pressing the button, like th sending a high pulse
+#[derive(Debug, Clone)]
+struct PulseCondition {
+    module: String,
+    direction: Direction,
+    high: bool,
+}
+
+impl PulseCondition {
+    // Parse th:high, th:sends:high or rx:gets:low
+    fn parse(s: &str) -> Result<PulseCondition, String> {
+        let tokens: Vec<&str> = s.split(':').collect();
+        let (module, direction, level) = match tokens[..] {
+            [m, l] => (m, Direction::Sends, l),
+            [m, "sends", l] => (m, Direction::Sends, l),
+            [m, "gets", l] => (m, Direction::Gets, l),
+            _ => return Err(format!("Watch {} should look like th:high or rx:gets:low", s)),
+        };
+        let high = match level {
+            "high" => true,
+            "low" => false,
+            _ => return Err(format!("Pulse has to be high or low, not {}", level)),
+        };
+        Ok(PulseCondition { module: module.to_string(), direction, high })
+    }
+
+    fn matches(&self, pulse: &Pulse) -> bool {
+        let module = match self.direction {
+            Direction::Sends => &pulse.from,
+            Direction::Gets => &pulse.to,
+        };
+        pulse.high == self.high && *module == self.module
+    }
+}
+
+impl fmt::Display for PulseCondition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let direction = match self.direction { Direction::Sends => "sends", Direction::Gets => "gets" };
+        write!(f, "{} {} {}", self.module, direction, if self.high { "high" } else { "low" })
+    }
+}
+
+// A watched condition happening, pulse is which pulse of the press it was, counting from 0
+#[derive(Debug)]
+struct Event {
+    watch: usize,
+    pulse: usize,
+}
+
+// Everything that happened during one button press
+#[derive(Debug)]
+struct PressRecord {
+    press: u64,
+    low: i64,
+    high: i64,
+    events: Vec<Event>,
+}
+
+impl PressRecord {
+    fn saw(&self, watch: usize) -> bool {
+        self.events.iter().any(|e| e.watch == watch)
+    }
+}
+
 // TODO: Make these labels string slices instead
 #[derive(Debug)]
 struct Module {
@@ -79,9 +152,91 @@ impl Module {
 }
 
 
+// A chain of flip-flops acting as a binary counter, lowest bit first
+//  The bits that feed the conjunction make up the count it fires on, and when it fires it sends
+//  to the rest of the bits so the counter rolls back over to 0
+#[derive(Debug)]
+struct Counter {
+    bits: Vec<String>,
+    conjunction: String,
+    period: u64,
+}
+
+// Split a trace into presses, they're separated by blank lines and # lines are comments
+fn parse_trace(contents: &str) -> Vec<Vec<String>> {
+    let mut presses: Vec<Vec<String>> = Vec::new();
+    let mut cur: Vec<String> = Vec::new();
+    for line in contents.lines().map(|l| l.trim()) {
+        if line.starts_with('#') {
+            continue;
+        }
+        if line.is_empty() {
+            if !cur.is_empty() {
+                presses.push(cur);
+                cur = Vec::new();
+            }
+        } else {
+            cur.push(line.to_string());
+        }
+    }
+    if !cur.is_empty() {
+        presses.push(cur);
+    }
+    presses
+}
+
+// Compare the presses in the expected trace against the ones we ran, first difference per press
+//  Extra presses in actual are fine, expected is usually just the first few from the puzzle
+fn diff_traces(expected: &[Vec<String>], actual: &[Vec<String>]) -> Vec<String> {
+    let mut diffs = Vec::new();
+    for (i, exp) in expected.iter().enumerate() {
+        let act = match actual.get(i) {
+            Some(a) => a,
+            None => {
+                diffs.push(format!("Presses {} to {}: missing, only ran {}", i + 1, expected.len(), actual.len()));
+                break;
+            },
+        };
+        let none = "nothing".to_string();
+        for line in 0..exp.len().max(act.len()) {
+            let (e, a) = (exp.get(line).unwrap_or(&none), act.get(line).unwrap_or(&none));
+            if e != a {
+                diffs.push(format!("Press {} pulse {}: expected {}, got {}", i + 1, line + 1, e, a));
+                break;
+            }
+        }
+    }
+    diffs
+}
+
+fn load_trace(filename: &str) -> Result<Vec<Vec<String>>, String> {
+    match fs::read_to_string(filename) {
+        Ok(c) => Ok(parse_trace(&c)),
+        Err(e) => Err(format!("Unable to read trace {}: {}", filename, e)),
+    }
+}
+
 pub struct Day20 {
     modules: HashMap<String, Module>,
     input_map: HashMap<String, Vec<String>>,
+    // Button presses so far
+    presses: u64,
+    // Extra conditions to report on every press
+    watches: Vec<PulseCondition>,
+    // How many presses for part a
+    press_count: u64,
+    // Give up on part b after this many presses
+    max_presses: u64,
+    // Check the counters we find against the simulation
+    verify: bool,
+    // Write every pulse like a -high-> inv here, a blank line after each press
+    trace_out: Option<Box<dyn Write>>,
+    // Keep the trace to compare against the expected trace in this file
+    expect: Option<String>,
+    expected: Vec<Vec<String>>,
+    recorded: Vec<Vec<String>>,
+    // Just compare these two trace files instead of running anything
+    diff: Vec<(String, Vec<Vec<String>>)>,
 }
 
 impl Day20 {
@@ -89,18 +244,28 @@ impl Day20 {
         Day20 {
             modules: HashMap::new(),
             input_map: HashMap::new(),
+            presses: 0,
+            watches: Vec::new(),
+            press_count: 1000,
+            max_presses: 10_000_000,
+            verify: true,
+            trace_out: None,
+            expect: None,
+            expected: Vec::new(),
+            recorded: Vec::new(),
+            diff: Vec::new(),
         }
     }
 
     fn initialize(&mut self) {
-        // Could iterate through the outputs for each module and define Output
-        //  Modules for every output that doesn't exist, but we'll take a shortcut
-        let output = Module {module_type: ModuleType::Output, 
-            label: "output".to_string(), outputs: Vec::new(), state: false, input_map: HashMap::new()};
-        self.modules.insert("output".to_string(), output);    
-        let rx = Module {module_type: ModuleType::Output, 
-            label: "rx".to_string(), outputs: Vec::new(), state: false, input_map: HashMap::new()};
-        self.modules.insert("rx".to_string(), rx);    
+        // Anything that gets pulses but isn't defined, like output or rx, just swallows them
+        for label in self.input_map.keys() {
+            if !self.modules.contains_key(label) {
+                let output = Module {module_type: ModuleType::Output,
+                    label: label.to_string(), outputs: Vec::new(), state: false, input_map: HashMap::new()};
+                self.modules.insert(label.to_string(), output);
+            }
+        }
 
         for module in self.modules.values_mut() {
             if let ModuleType::Conjunction = module.module_type {
@@ -118,49 +283,335 @@ impl Day20 {
         }
     }
 
-    fn press_button(&mut self) -> (i64, i64, bool, bool, bool, bool) {
+    // Back to everything off, like before the first press
+    fn reset(&mut self) {
+        for module in self.modules.values_mut() {
+            module.state = false;
+            for v in module.input_map.values_mut() {
+                *v = false;
+            }
+        }
+        self.presses = 0;
+        self.recorded.clear();
+    }
+
+    // Only need to keep presses until we have as many as the expected trace
+    fn recording(&self) -> bool {
+        self.expect.is_some() && self.recorded.len() < self.expected.len()
+    }
+
+    // Push the button and run until the pulses die out, recording any of the watches we see
+    fn press_button(&mut self, watches: &[PulseCondition]) -> PressRecord {
+        self.presses += 1;
+        let mut record = PressRecord { press: self.presses, low: 0, high: 0, events: Vec::new() };
         let pulse = Pulse{from: "button".to_string(), high: false, to: "broadcaster".to_string()};
-        let mut pulses = Vec::new();
-        pulses.push(pulse);
+        let mut pulses = vec![pulse];
+        let mut pulse_idx = 0;
+        let recording = self.recording();
+        let tracing = self.trace_out.is_some() || recording;
+        let mut trace: Vec<String> = Vec::new();
 
-        let mut high_count = 0;
-        let mut low_count = 0;
-        let mut high_th = false;
-        let mut high_ch = false;
-        let mut high_sv = false;
-        let mut high_gh = false;
-        while pulses.len() > 0 {
+        while !pulses.is_empty() {
             let mut new_pulses: Vec<Pulse> = Vec::new();
             for p in &pulses {
-                //println!("{:?}", p);
-                if p.high { high_count +=1 } else { low_count += 1 };
-                if p.from.eq("th") && p.high == true {
-                    high_th = true;
+                if p.high { record.high += 1 } else { record.low += 1 };
+                if tracing {
+                    trace.push(format!("{} -{}-> {}", p.from, if p.high { "high" } else { "low" }, p.to));
+                }
+                for (watch, cond) in watches.iter().enumerate() {
+                    if cond.matches(p) {
+                        record.events.push(Event { watch, pulse: pulse_idx });
+                    }
+                }
+                pulse_idx += 1;
+                let m = match self.modules.get_mut(&p.to) {
+                    Some(m) => m,
+                    None => continue,
+                };
+                new_pulses.extend(m.pulse(p));
+            }
+            pulses = new_pulses;
+        }
+        if tracing {
+            self.write_trace(&trace);
+            if recording {
+                self.recorded.push(trace);
+            }
+        }
+        record
+    }
+
+    fn write_trace(&mut self, trace: &[String]) {
+        if let Some(out) = self.trace_out.as_mut() {
+            let mut result = Ok(());
+            for line in trace {
+                result = result.and_then(|_| writeln!(out, "{}", line));
+            }
+            result = result.and_then(|_| writeln!(out));
+            if let Err(e) = result {
+                eprintln!("Unable to write trace, turning it off: {}", e);
+                self.trace_out = None;
+            }
+        }
+    }
+
+    // Flush the trace, and check it against the expected one if there is one
+    fn finish_trace(&mut self) {
+        if let Some(out) = self.trace_out.as_mut() {
+            if let Err(e) = out.flush() {
+                eprintln!("Unable to write trace: {}", e);
+            }
+        }
+        let filename = match &self.expect {
+            Some(f) => f,
+            None => return,
+        };
+        let diffs = diff_traces(&self.expected, &self.recorded);
+        if diffs.is_empty() {
+            println!("Trace matches {} for all {} presses", filename, self.expected.len());
+        } else {
+            println!("Trace differs from {}:", filename);
+            for d in diffs {
+                println!("  {}", d);
+            }
+        }
+    }
+
+    // Diff the two trace files from the diff option, the answer is how many presses differ
+    fn diff_files(&self) -> String {
+        let ((file_a, trace_a), (file_b, trace_b)) = (&self.diff[0], &self.diff[1]);
+        let mut diffs = diff_traces(trace_a, trace_b);
+        if trace_b.len() > trace_a.len() {
+            diffs.push(format!("Presses {} to {}: only in {}", trace_a.len() + 1, trace_b.len(), file_b));
+        }
+        if diffs.is_empty() {
+            println!("{} and {} match for all {} presses", file_a, file_b, trace_a.len());
+        } else {
+            println!("{} and {} differ:", file_a, file_b);
+            for d in &diffs {
+                println!("  {}", d);
+            }
+        }
+        diffs.len().to_string()
+    }
+
+    fn print_events(&self, record: &PressRecord, watches: &[PulseCondition]) {
+        for e in &record.events {
+            println!("Press {} pulse {}: {}", record.press, e.pulse, watches[e.watch]);
+        }
+    }
+
+    // rx usually hangs off a single conjunction, which sends low once all of its inputs were high
+    //  Those inputs are what we watch for part b
+    fn find_rx_feeders(&self) -> Result<Vec<String>, String> {
+        let rx_inputs = match self.input_map.get("rx") {
+            Some(i) => i,
+            None => return Err("Nothing sends pulses to rx".to_string()),
+        };
+        if rx_inputs.len() != 1 {
+            return Err(format!("rx has {} inputs, not a single conjunction", rx_inputs.len()));
+        }
+        let feeder = &rx_inputs[0];
+        match self.modules.get(feeder).map(|m| &m.module_type) {
+            Some(ModuleType::Conjunction) => (),
+            _ => return Err(format!("{} feeds rx but isn't a conjunction", feeder)),
+        }
+        match self.input_map.get(feeder) {
+            Some(inputs) => Ok(inputs.clone()),
+            None => Err(format!("Nothing sends pulses to {}", feeder)),
+        }
+    }
+
+    // Press until every feeder of the rx conjunction has sent a high pulse twice
+    //  If each one's second high is twice its first they're clean cycles from the start, and they
+    //  all line up at the lcm
+    fn solve_with_feeders(&mut self, feeders: &[String]) -> Option<u64> {
+        println!("rx is fed through a conjunction from {:?}", feeders);
+        let mut watches: Vec<PulseCondition> = feeders.iter()
+            .map(|f| PulseCondition { module: f.clone(), direction: Direction::Sends, high: true })
+            .collect();
+        let first_user_watch = watches.len();
+        watches.extend(self.watches.iter().cloned());
+
+        let mut seen: Vec<Vec<u64>> = vec![Vec::new(); feeders.len()];
+        while seen.iter().any(|s| s.len() < 2) {
+            if self.presses >= self.max_presses {
+                eprintln!("No cycles after {} presses", self.presses);
+                return None;
+            }
+            let record = self.press_button(&watches);
+            for (i, s) in seen.iter_mut().enumerate() {
+                if record.saw(i) && s.len() < 2 {
+                    s.push(record.press);
                 }
-                if p.from.eq("ch") && p.high == true {
-                    high_ch = true;
+            }
+            if record.events.iter().any(|e| e.watch >= first_user_watch) {
+                self.print_events(&record, &watches);
+            }
+        }
+
+        let mut result: u64 = 1;
+        for (feeder, s) in feeders.iter().zip(&seen) {
+            println!("{} first sends high on press {}, then {}", feeder, s[0], s[1]);
+            if s[1] != 2 * s[0] {
+                println!("Warning: {} doesn't repeat every {} presses, the lcm may be wrong", feeder, s[0]);
+            }
+            result = num::integer::lcm(result, s[0]);
+        }
+        Some(result)
+    }
+
+    // Every output of the broadcaster should start a counter, follow each flip-flop to the next one
+    fn find_counters(&self) -> Result<Vec<Counter>, String> {
+        let starts = match self.modules.get("broadcaster") {
+            Some(b) => &b.outputs,
+            None => return Err("There's no broadcaster".to_string()),
+        };
+        let mut counters = Vec::new();
+        for start in starts {
+            let mut bits: Vec<String> = Vec::new();
+            let mut feeds_conj: Vec<bool> = Vec::new();
+            let mut conjunction: Option<&String> = None;
+            let mut cur = Some(start);
+            while let Some(label) = cur {
+                let module = &self.modules[label];
+                if !matches!(module.module_type, ModuleType::FlipFlop) {
+                    return Err(format!("{} in the counter from {} isn't a flip-flop", label, start));
                 }
-                if p.from.eq("sv") && p.high == true {
-                    high_sv = true;
+                if bits.contains(label) {
+                    return Err(format!("Counter from {} loops back to {}", start, label));
                 }
-                if p.from.eq("gh") && p.high == true {
-                    high_gh = true;
+                bits.push(label.clone());
+                cur = None;
+                let mut feeds = false;
+                for o in &module.outputs {
+                    match self.modules[o].module_type {
+                        ModuleType::FlipFlop if cur.is_none() => cur = Some(o),
+                        ModuleType::FlipFlop => return Err(format!("{} feeds more than one flip-flop", label)),
+                        ModuleType::Conjunction if conjunction.is_none_or(|c| c == o) => {
+                            conjunction = Some(o);
+                            feeds = true;
+                        },
+                        _ => return Err(format!("{} feeds {}, which isn't part of its counter", label, o)),
+                    }
                 }
-                let m = self.modules.get_mut(&p.to).unwrap();
-                let output_pulses = m.pulse(p);
-                for op in output_pulses {
-                    new_pulses.push(op);
+                feeds_conj.push(feeds);
+            }
+            let conjunction = match conjunction {
+                Some(c) => c.clone(),
+                None => return Err(format!("Counter from {} never feeds a conjunction", start)),
+            };
+            let period = feeds_conj.iter().rev().fold(0, |p, f| (p << 1) | *f as u64);
+            counters.push(Counter { bits, conjunction, period });
+        }
+        Ok(counters)
+    }
+
+    // Press until every feeder has sent a high pulse, the counter behind each one should fire
+    //  for the first time on its period
+    fn verify_counters(&mut self, counters: &[Counter], feeders: &[String]) -> bool {
+        let watches: Vec<PulseCondition> = feeders.iter()
+            .map(|f| PulseCondition { module: f.clone(), direction: Direction::Sends, high: true })
+            .collect();
+        let limit = counters.iter().map(|c| c.period).max().unwrap_or(0);
+        let mut first: Vec<Option<u64>> = vec![None; feeders.len()];
+        while self.presses < limit && first.iter().any(|f| f.is_none()) {
+            let record = self.press_button(&watches);
+            for (i, f) in first.iter_mut().enumerate() {
+                if f.is_none() && record.saw(i) {
+                    *f = Some(record.press);
                 }
             }
-            pulses.clear();
-            pulses = new_pulses;
         }
-        
-        (low_count, high_count, high_ch, high_gh, high_sv, high_th)
+        self.reset();
+
+        let mut ok = true;
+        for (feeder, f) in feeders.iter().zip(&first) {
+            // The feeder is the counter's conjunction, or hangs right off of it
+            let counter = counters.iter().find(|c| c.conjunction == *feeder || self.modules[&c.conjunction].outputs.contains(feeder));
+            match (counter, f) {
+                (Some(c), Some(press)) if c.period == *press => {
+                    println!("{} sends high on press {}, matching counter {}", feeder, press, c.conjunction);
+                },
+                (Some(c), _) => {
+                    println!("{} first sends high on press {:?}, but counter {} has period {}", feeder, f, c.conjunction, c.period);
+                    ok = false;
+                },
+                (None, _) => {
+                    println!("{} isn't driven by any counter", feeder);
+                    ok = false;
+                },
+            }
+        }
+        ok
+    }
+
+    // Read the answer off of the counters, instead of pressing the button
+    fn solve_with_counters(&mut self, feeders: &[String]) -> Result<u64, String> {
+        let counters = self.find_counters()?;
+        let mut result: u64 = 1;
+        for c in &counters {
+            println!("Counter {} through {:?}, fires every {} = {:b} presses", c.conjunction, c.bits, c.period, c.period);
+            result = num::integer::lcm(result, c.period);
+        }
+        if self.verify && !self.verify_counters(&counters, feeders) {
+            return Err("Counters don't match the simulation".to_string());
+        }
+        Ok(result)
+    }
+
+    // Just press the button until rx gets a low pulse
+    fn solve_by_simulation(&mut self) -> Option<u64> {
+        let mut watches = vec![PulseCondition { module: "rx".to_string(), direction: Direction::Gets, high: false }];
+        watches.extend(self.watches.iter().cloned());
+        while self.presses < self.max_presses {
+            let record = self.press_button(&watches);
+            if record.events.iter().any(|e| e.watch > 0) {
+                self.print_events(&record, &watches);
+            }
+            if record.saw(0) {
+                return Some(record.press);
+            }
+        }
+        eprintln!("rx never got a low pulse in {} presses", self.presses);
+        None
     }
 }
 
 impl AOCProblem for Day20 {
+    fn configure(&mut self, config: &Config) -> Result<(), String> {
+        self.press_count = config.get_option("presses")?.unwrap_or(1000);
+        self.max_presses = config.get_option("max_presses")?.unwrap_or(10_000_000);
+        self.verify = config.get_option("verify")?.unwrap_or(true);
+        self.expect = config.get_option("expect")?;
+        if let Some(filename) = &self.expect {
+            self.expected = load_trace(filename)?;
+        }
+        if let Some(files) = config.get_option::<String>("diff")? {
+            let names: Vec<&str> = files.split(',').collect();
+            if names.len() != 2 {
+                return Err(format!("diff={} should be two trace files like a.txt,b.txt", files));
+            }
+            for name in names {
+                self.diff.push((name.to_string(), load_trace(name)?));
+            }
+        }
+        match config.get_option::<String>("trace")?.as_deref() {
+            None => (),
+            Some("-") => self.trace_out = Some(Box::new(io::stdout())),
+            Some(filename) => match File::create(filename) {
+                Ok(f) => self.trace_out = Some(Box::new(BufWriter::new(f))),
+                Err(e) => return Err(format!("Unable to create trace {}: {}", filename, e)),
+            },
+        }
+        if let Some(w) = config.get_option::<String>("watch")? {
+            for token in w.split(',') {
+                self.watches.push(PulseCondition::parse(token)?);
+            }
+        }
+        Ok(())
+    }
+
     fn handle_line(&mut self, line: &str, _config: &Config) {
         let mut line_iter = line.split("->");
         let lbl = line_iter.next().unwrap().trim();
@@ -205,61 +656,56 @@ impl AOCProblem for Day20 {
     }
     
     fn compute_a(&mut self) -> String {
+        if !self.diff.is_empty() {
+            return self.diff_files();
+        }
         println!("Input Map: {:?}", self.input_map);
         self.initialize();
         for m in &self.modules {
             println!("{} = {:?}", m.0, m.1);
         }
         // Let's try without caching or cycle detection
+        let watches = self.watches.clone();
         let mut low_count = 0;
         let mut high_count = 0;
-        for _ in 0..1000 {
-            let counts = self.press_button();            
-            println!("Low Count: {}", counts.0);
-            println!("High Count: {}", counts.1);
-            println!("Single Low RX: {}", counts.2);
-            low_count += counts.0;
-            high_count += counts.1;
+        for _ in 0..self.press_count {
+            let record = self.press_button(&watches);
+            println!("Low Count: {}", record.low);
+            println!("High Count: {}", record.high);
+            self.print_events(&record, &watches);
+            low_count += record.low;
+            high_count += record.high;
         }
         println!("Total Low Count: {}", low_count);
         println!("Total High Count: {}", high_count);
+        self.finish_trace();
         (low_count * high_count).to_string()
     }
 
     fn compute_b(&mut self) -> String {
-        // Surely this won't work, just simulating it
-
-        // &cn -> rx 
-        // th, sv, gh, ch -> cn 
-        // when do all th, sv, gh, ch go high?
-        
-        // Looks like they go high every so often, in a cycle
-        // LCM of the cycles should be when they're all high
-        // This only works for this specific input
+        if !self.diff.is_empty() {
+            return self.diff_files();
+        }
+        // Simulating all the way to rx would take forever, the network is built out of counters
+        //  that each fire every so often, rx only gets its low pulse once they all line up
         self.initialize();
-        let mut presses = 1;
-        let mut high_inputs: (i64, i64, i64, i64) = (0, 0, 0, 0);
-        loop {
-            let counts = self.press_button();
-            if counts.2 && high_inputs.0 == 0 {
-                high_inputs.0 = presses;
-            }
-            if counts.3 && high_inputs.1 == 0 {
-                high_inputs.1 = presses;
-            }
-            if counts.4 && high_inputs.2 == 0 {
-                high_inputs.2 = presses;
-            }
-            if counts.5 && high_inputs.3 == 0 {
-                high_inputs.3 = presses;
-            }
-            match high_inputs {
-                (0,_,_,_) | (_,0,_,_) | (_,_,0,_) | (_,_,_,0)=> (),
-                _ => break,
-            }
-            presses += 1;
+        let result = match self.find_rx_feeders() {
+            Ok(feeders) => match self.solve_with_counters(&feeders) {
+                Ok(r) => Some(r),
+                Err(e) => {
+                    println!("{}, watching the rx conjunction's inputs instead", e);
+                    self.solve_with_feeders(&feeders)
+                },
+            },
+            Err(e) => {
+                println!("{}, simulating until rx gets a low pulse", e);
+                self.solve_by_simulation()
+            },
+        };
+        self.finish_trace();
+        match result {
+            Some(presses) => presses.to_string(),
+            None => "Error".to_string(),
         }
-        println!("High Input first seen: {:?}", high_inputs);
-        (high_inputs.0 * high_inputs.1 * high_inputs.2 * high_inputs.3).to_string()
     }
-}
\ No newline at end of file
+}
@@ -1,7 +1,19 @@
 use crate::{Config, AOCProblem};
+use std::collections::VecDeque;
+
+// The puzzle's step counts, part a is smaller for the test garden
+const PART_A_STEPS: u64 = 64;
+const PART_A_TEST_STEPS: u64 = 6;
+const PART_B_STEPS: u64 = 26501365;
+// How many later starting points to try if the first quadratic fit doesn't validate
+const FIT_ATTEMPTS: u64 = 5;
 
 pub struct Day21 {
     board: Vec<Vec<char>>,
+    // Steps to take, on the single garden for part a or the tiled map for part b
+    steps: u64,
+    // How many extra board widths to check the quadratic fit against brute force
+    validate: u64,
 }
 
 fn _print_board(board: &Vec<Vec<char>>) {
@@ -16,213 +28,185 @@ impl Day21 {
     pub fn new() -> Day21 {
         Day21 {
             board: Vec::new(),
+            steps: PART_B_STEPS,
+            validate: 2,
         }
     }
 
-    fn update_board(&mut self) {
-        // Update positions we can step on
-        let mut new_stepped: Vec<(usize, usize)> = Vec::new();
+    // Where the S is, or the middle of a square garden if there isn't one
+    fn find_start(&self) -> Option<(usize, usize)> {
+        for (i, row) in self.board.iter().enumerate() {
+            if let Some(j) = row.iter().position(|c| *c == 'S') {
+                return Some((i, j));
+            }
+        }
+        let size = self.board.len();
+        if size % 2 == 1 && self.board.iter().all(|r| r.len() == size) {
+            println!("Warning: no S on the board, starting in the middle");
+            return Some((size / 2, size / 2));
+        }
+        None
+    }
 
-        for i in 0..self.board.len() {
-            for j in 0..self.board[i].len() {
-                if self.board[i][j] == 'O' || self.board[i][j] == 'S' {
-                    if i > 0 && self.board[i-1][j] != '#' { 
-                        new_stepped.push((i-1,j));
+    // Breadth first out from the start across the map tiled forever in every direction
+    //  Returns how many plots are first reached at each distance, up to max_steps
+    fn tiled_bfs(&self, start: (usize, usize), max_steps: usize) -> Vec<u64> {
+        let height = self.board.len() as i64;
+        let width = self.board[0].len() as i64;
+        // Nothing past max_steps matters, so a square that big around the start holds everything
+        let side = 2 * max_steps + 1;
+        let mut seen = vec![false; side * side];
+        let idx = |i: i64, j: i64| (i as usize) * side + j as usize;
+        let origin = max_steps as i64;
+
+        let mut counts: Vec<u64> = vec![1];
+        let mut frontier: Vec<(i64, i64)> = vec![(origin, origin)];
+        seen[idx(origin, origin)] = true;
+        for _ in 0..max_steps {
+            let mut next: Vec<(i64, i64)> = Vec::new();
+            for (i, j) in frontier {
+                for (ni, nj) in [(i - 1, j), (i + 1, j), (i, j - 1), (i, j + 1)] {
+                    if ni < 0 || nj < 0 || ni >= side as i64 || nj >= side as i64 || seen[idx(ni, nj)] {
+                        continue;
                     }
-                    if i < self.board.len() - 1 && self.board[i+1][j] != '#' {
-                        new_stepped.push((i+1,j));
+                    // Back to board coordinates, wrapping around the tiles
+                    let bi = (ni - origin + start.0 as i64).rem_euclid(height) as usize;
+                    let bj = (nj - origin + start.1 as i64).rem_euclid(width) as usize;
+                    if self.board[bi][bj] == '#' {
+                        continue;
                     }
-                    if j > 0 && self.board[i][j-1] != '#' {
-                        new_stepped.push((i, j-1));
-                    }
-                    if j < self.board[i].len() - 1 && self.board[i][j+1] != '#' {
-                        new_stepped.push((i, j+1));
-                    }
-                    self.board[i][j] = '.';
+                    seen[idx(ni, nj)] = true;
+                    next.push((ni, nj));
                 }
             }
+            counts.push(next.len() as u64);
+            frontier = next;
         }
+        counts
+    }
 
-        for new_pos in new_stepped {
-            self.board[new_pos.0][new_pos.1] = 'O';
-        }
+    // A plot is reachable in exactly n steps if we can get there in n or less with the same parity,
+    //  we can always step back and forth to waste 2 steps
+    fn reachable(counts: &[u64], n: u64) -> u64 {
+        counts.iter().take(n as usize + 1).skip(n as usize % 2).step_by(2).sum()
     }
 
-    fn count_stepped(&self) -> usize {
-        let mut count = 0;
-        for i in 0..self.board.len() {
-            count += (&self.board[i]).iter().filter(|&n| *n == 'O').count();
+    // On a square garden the reachable count grows quadratically every board width once the diamond
+    //  of boards settles down, f(r + k * size) = a k^2 + b k + c where r = steps % size
+    //  Fit on 3 widths starting at k0, check it against brute force on the next few, and if it doesn't
+    //  hold try again further out
+    fn solve_tiled(&self, steps: u64) -> Result<u64, String> {
+        let start = match self.find_start() {
+            Some(s) => s,
+            None => return Err("No S on the board, and no middle to start from".to_string()),
+        };
+        let size = self.board.len() as u64;
+        if self.board.iter().any(|r| r.len() as u64 != size) {
+            return Err("The garden has to be square".to_string());
+        }
+        if start != (size as usize / 2, size as usize / 2) {
+            println!("Warning: start {:?} isn't in the middle of the garden, the fit may not settle", start);
+        }
+
+        let r = steps % size;
+        let k_target = steps / size;
+        let needed = |k0: u64| r + (k0 + 2 + self.validate) * size;
+        if steps <= needed(0) {
+            println!("{} steps is small enough to just walk", steps);
+            let counts = self.tiled_bfs(start, steps as usize);
+            return Ok(Day21::reachable(&counts, steps));
+        }
+
+        for k0 in 0..FIT_ATTEMPTS {
+            let counts = self.tiled_bfs(start, needed(k0) as usize);
+            let f = |k: u64| Day21::reachable(&counts, r + k * size) as i128;
+            // Newton forward differences, exact in integers
+            let (y0, d1, d2) = (f(k0), f(k0 + 1) - f(k0), f(k0 + 2) - 2 * f(k0 + 1) + f(k0));
+            let predict = |k: u64| {
+                let t = (k - k0) as i128;
+                y0 + t * d1 + t * (t - 1) / 2 * d2
+            };
+            println!("Fit from {} steps: f(k) = {} + {} t + {} t(t-1)/2, t = k - {}", r + k0 * size, y0, d1, d2, k0);
+
+            let mut ok = true;
+            for k in k0 + 3..k0 + 3 + self.validate {
+                let (actual, predicted) = (f(k), predict(k));
+                println!("  {} steps: brute force {} fit {}", r + k * size, actual, predicted);
+                ok &= actual == predicted;
+            }
+            if ok {
+                if k_target < k0 {
+                    return Ok(Day21::reachable(&counts, steps));
+                }
+                return Ok(predict(k_target) as u64);
+            }
         }
-        count
+        Err(format!("The quadratic fit didn't hold after {} tries", FIT_ATTEMPTS))
     }
 
+    // Steps from the start to every plot on the garden, None for rocks and plots we can't get to
+    fn distances(&self, start: (usize, usize)) -> Vec<Vec<Option<usize>>> {
+        let mut dist: Vec<Vec<Option<usize>>> = self.board.iter().map(|r| vec![None; r.len()]).collect();
+        let mut work: VecDeque<(usize, usize)> = VecDeque::new();
+        dist[start.0][start.1] = Some(0);
+        work.push_back(start);
+        while let Some((i, j)) = work.pop_front() {
+            let d = dist[i][j].unwrap();
+            let neighbors = [(i.wrapping_sub(1), j), (i + 1, j), (i, j.wrapping_sub(1)), (i, j + 1)];
+            for (ni, nj) in neighbors {
+                if ni < self.board.len() && nj < self.board[ni].len() && self.board[ni][nj] != '#' && dist[ni][nj].is_none() {
+                    dist[ni][nj] = Some(d + 1);
+                    work.push_back((ni, nj));
+                }
+            }
+        }
+        dist
+    }
 }
 
 impl AOCProblem for Day21 {
+    fn configure(&mut self, config: &Config) -> Result<(), String> {
+        let default_steps = match (config.variant, config.test_input) {
+            (true, _) => PART_B_STEPS,
+            (false, true) => PART_A_TEST_STEPS,
+            (false, false) => PART_A_STEPS,
+        };
+        self.steps = config.get_option("steps")?.unwrap_or(default_steps);
+        self.validate = config.get_option("validate")?.unwrap_or(2);
+        Ok(())
+    }
+
     fn handle_line(&mut self, line: &str, _config: &Config) {
         let row: Vec<char> = line.chars().collect();
         self.board.push(row);
     }
     
-    // Just count the items in the list
+    // Plots we can be on after exactly steps, without leaving the garden
     fn compute_a(&mut self) -> String {
-        //print_board(&self.board);
-        for i in 0..350 {
-            self.update_board();
-            let count = self.count_stepped();
-            //print_board(&self.board);
-            println!("Step: {} Squares: {}", i, count);
+        let start = match self.find_start() {
+            Some(s) => s,
+            None => { eprintln!("No S on the board, and no middle to start from"); return "Error".to_string(); },
+        };
+        let dist = self.distances(start);
+        // Same counting as the tiled map, how many plots are first reached at each distance
+        let mut counts: Vec<u64> = Vec::new();
+        for d in dist.iter().flatten().flatten() {
+            if *d >= counts.len() {
+                counts.resize(d + 1, 0);
+            }
+            counts[*d] += 1;
         }
-        self.count_stepped().to_string()
+        println!("Farthest plot is {} steps from {:?}, {} plots reachable", counts.len() - 1, start, counts.iter().sum::<u64>());
+        Day21::reachable(&counts, self.steps).to_string()
     }
 
     fn compute_b(&mut self) -> String {
-        // Will need to experiment with the details of the input
-        // After Step 131 we enter a cycle on the single board, alternating between 7509 and 7566 squares
-        // There's no obstacle straight from S in any direction
-        //   So after X steps, we stepped on a square X out in each direction from S
-        //   S is in the middle, 65 steps from any end
-        // (26501365 - 65) / 131 = 202,300
-        //   We've walked 202,300 boards in each direction
-        //     This forms a diamond
-        //            O
-        //           OOO
-        //          OOOOO 
-        //           OOO
-        //            O
-        //  Size of the diamond is (2N - 1) + 2(2N - 3) + 2(2N - 5) ...
-        // Could lookup the formula or solve, but lets just compute
-        let n = 202301;
-        let mut boards: i64 = (2*n) - 1;
-        let mut odd = 1;
-        for _ in 0..n-1 {
-            odd += 2;
-            boards += 2 * ((2*n) - odd);
-        }
-        // 81850984601 boards in the diamond
-        // How many boards are in one state vs the other?
-        // Parity flips when we move to the next board
-        //               O
-        //        E     OEO
-        //       EOE   OEOEO
-        //        E     OEO
-        //               O
-        // N^2 odd, (N-1)^2 even
-        
-        // Some boards are incomplete though
-        // Test board on the perimeter
-        // Board on the left, we started at the right most center position (straight from S)
-        // How far do we get in 131 steps
-        let orig_board = self.board.clone();
-        self.board[65][65] = '.';
-        self.board[65][130] = 'S';
-        for _ in 0..131 {
-            self.update_board();
-        }
-
-        println!("Left board in {} steps = {} count", 131, self.count_stepped());
-        self.board[65][65] = '.';
-        self.board[130][65] = 'S';
-        self.board[65][130] = 'S';
-        for _ in 0..131 {
-            self.update_board();
-        }
-        println!("Left board 2 in {} steps = {} count", 131, self.count_stepped() / 2);
-
-        // Now Right board
-        self.board = orig_board.clone();
-        self.board[65][65] = '.';
-        self.board[65][0] = 'S';
-        for _ in 0..131 {
-            self.update_board();
-        }
-        println!("Right board in {} steps = {} count", 131, self.count_stepped());
-
-        // Down
-        self.board = orig_board.clone();
-        self.board[65][65] = '.';
-        self.board[0][65] = 'S';
-        for _ in 0..131 {
-            self.update_board();
-        }
-        println!("Down board in {} steps = {} count", 131, self.count_stepped());
-
-        // Up
-        self.board = orig_board.clone();
-        self.board[65][65] = '.';
-        self.board[130][65] = 'S';
-        for _ in 0..131 {
-            self.update_board();
-        }
-        println!("Up board in {} steps = {} count", 131, self.count_stepped());
-
-        // Corners, we take half the steps to get to the corner board, then only have half the steps
-        self.board = orig_board.clone();
-        self.board[65][65] = '.';
-        self.board[0][0] = 'S';
-        for _ in 0..65 {
-            self.update_board();
-        }
-        let se_count = self.count_stepped();
-        println!("SE board in {} steps = {} count", 65, se_count);
-
-        self.board = orig_board.clone();
-        self.board[65][65] = '.';
-        self.board[0][130] = 'S';
-        for _ in 0..65 {
-            self.update_board();
-        }
-        let sw_count = self.count_stepped();
-        println!("SW board in {} steps = {} count", 65, sw_count);
-        self.board = orig_board.clone();
-        self.board[65][65] = '.';
-        self.board[130][0] = 'S';
-        for _ in 0..65 {
-            self.update_board();
-        }
-        let ne_count = self.count_stepped();
-        println!("NE board in {} steps = {} count", 65, ne_count);
-
-        self.board = orig_board.clone();
-        self.board[65][65] = '.';
-        self.board[130][130] = 'S';
-        for _ in 0..65 {
-            self.update_board();
+        match self.solve_tiled(self.steps) {
+            Ok(count) => count.to_string(),
+            Err(e) => {
+                eprintln!("{}", e);
+                "Error".to_string()
+            },
         }
-        let nw_count = self.count_stepped();
-        println!("NW board in {} steps = {} count", 65, nw_count);
-
-        // Full boards, ignore the perimeter
-        let odd_boards = (n - 1) * (n - 1);
-        let even_boards = (n - 2) * (n - 2);
-
-        let total_interior_boards = odd_boards + even_boards;
-        let perimeter_boards = (boards - total_interior_boards) / 4;
-        let b1 = even_boards * 7509;
-        let b2: i64 = odd_boards * 7566;
-
-        // These counts are all off by some: TBD figure out
-        //  The simulations above are likely wrong
-        //  Instead, simulate entirely the n=2 version and check each square
-
-        let b3 = (perimeter_boards * 5781) + (perimeter_boards * 5793) + (perimeter_boards * 5793) + (perimeter_boards * 5781);
-        // Finally add the corner boards, looks like there's n-1 corner boards of each type
-        let corner_count = n - 1;
-
-        let b4: i64 = corner_count * se_count as i64;
-        let b5: i64 = corner_count * sw_count as i64;
-        let b6: i64 = corner_count * ne_count as i64;
-        let b7: i64 = corner_count * nw_count as i64;
-
-        println!("Boards: {}  Odd Boards {} Even Boards {} Total Interior Boards {} Total Perimeter {} Total {}",
-            boards, odd_boards, even_boards, total_interior_boards, perimeter_boards, total_interior_boards + perimeter_boards);
-        println!("Corners: {} SE {} SW {} NE {} NW {}", corner_count, b4,b5,b6,b7);
-        let corners = b4+b5+b6+b7;
-        println!("Corners: {}", corners);
-
-        let ans = b1 + b2 + b3 + b4 + b5 + b6 + b7;
-        let diff: i64 = ans - 616951804315987;
-        println!("diff: {}", diff);
-        ans.to_string()
     }
 }
@@ -33,94 +33,132 @@ impl Brick {
         }
     }
 
-    // New brick down 1
-    fn drop_1(&self) -> Brick {
-        if self.start.z > 1 && self.end.z > 1 {
-            Brick{start: Position{x: self.start.x, y: self.start.y, z: self.start.z - 1},
-                  end: Position{x: self.end.x, y: self.end.y, z: self.end.z - 1}, index: self.index}
-        } else {
-            self.clone()
-        }
+    fn height(&self) -> u32 {
+        self.start.z.abs_diff(self.end.z) + 1
     }
 
-    fn does_overlap(&self, other: &Brick) -> bool {
-        let lx = std::cmp::min(self.start.x, self.end.x);
-        let ux = std::cmp::max(self.start.x, self.end.x);
-        let ly = std::cmp::min(self.start.y, self.end.y);
-        let uy = std::cmp::max(self.start.y, self.end.y);
-        let lz = std::cmp::min(self.start.z, self.end.z);
-        let uz = std::cmp::max(self.start.z, self.end.z);
-
-        let lx2 = std::cmp::min(other.start.x, other.end.x);
-        let ux2 = std::cmp::max(other.start.x, other.end.x);
-        let ly2 = std::cmp::min(other.start.y, other.end.y);
-        let uy2 = std::cmp::max(other.start.y, other.end.y);
-        let lz2 = std::cmp::min(other.start.z, other.end.z);
-        let uz2 = std::cmp::max(other.start.z, other.end.z);
-
-        if (lx <= ux2 && ux >= lx2) && (ly <= uy2 && uy >= ly2) && (lz <= uz2 && uz >= lz2) {
-            return true;
+    // Every x,y column the brick covers
+    fn footprint(&self) -> Vec<(usize, usize)> {
+        let (lx, ux) = (self.start.x.min(self.end.x), self.start.x.max(self.end.x));
+        let (ly, uy) = (self.start.y.min(self.end.y), self.start.y.max(self.end.y));
+        let mut cells = Vec::new();
+        for x in lx..=ux {
+            for y in ly..=uy {
+                cells.push((x as usize, y as usize));
+            }
         }
-        return false;
+        cells
     }
 
-    // Does this brick fit with these bricks, or do any of them overlap
-    //  Optionally give a brick in the lower_brick vec to ignore
-    fn does_fit(&self, lower_bricks: &Vec<Brick>, ignore: Option<&Brick>) -> bool {
-        for brick in lower_bricks {
-            if let Some(b) = ignore {
-                if brick.eq(b) {
-                    continue;
-                }
-            }
-            if self.index == brick.index {
-                // Don't overlap myself
-                continue;
-            }
-            if self.does_overlap(brick) {
-                return false;
-            }
-        }
-        return true;
+    // Same brick moved so its bottom is at z
+    fn moved_to(&self, z: u32) -> Brick {
+        let drop = self.lowest_z() - z;
+        Brick{start: Position{x: self.start.x, y: self.start.y, z: self.start.z - drop},
+              end: Position{x: self.end.x, y: self.end.y, z: self.end.z - drop}, index: self.index}
     }
+}
 
-    fn drop_brick(&self, lower_bricks: &Vec<Brick>) -> Brick {
-        // Try to drop the brick one by one, until we can't
-        let mut cur_brick = self.clone();
-        loop {
-            let lz = cur_brick.lowest_z();
-            if lz == 1 {
-                // We're at the bottom
-                return cur_brick;
+// The bricks after they've all fallen, in the order they landed, so every brick comes after the
+//  bricks holding it up
+//  supports[i] is the bricks resting on brick i, supported_by[i] is the bricks brick i rests on
+struct Stack {
+    bricks: Vec<Brick>,
+    supports: Vec<Vec<usize>>,
+    supported_by: Vec<Vec<usize>>,
+}
+
+impl Stack {
+    // Drop the bricks lowest first, keeping the top of the pile in each x,y column and which
+    //  brick it is, so each brick lands one above the highest top under its footprint
+    fn settle(items: &[Brick]) -> Stack {
+        let mut falling: Vec<&Brick> = items.iter().collect();
+        falling.sort_by_key(|b| b.lowest_z());
+        let max_x = items.iter().map(|b| b.start.x.max(b.end.x)).max().unwrap_or(0) as usize;
+        let max_y = items.iter().map(|b| b.start.y.max(b.end.y)).max().unwrap_or(0) as usize;
+        let mut heights: Vec<Vec<(u32, Option<usize>)>> = vec![vec![(0, None); max_y + 1]; max_x + 1];
+
+        let mut stack = Stack { bricks: Vec::new(), supports: Vec::new(), supported_by: Vec::new() };
+        for brick in falling {
+            let footprint = brick.footprint();
+            let rest = footprint.iter().map(|(x, y)| heights[*x][*y].0).max().unwrap_or(0);
+            let mut below: Vec<usize> = footprint.iter()
+                .filter_map(|(x, y)| match heights[*x][*y] { (z, Some(b)) if z == rest => Some(b), _ => None })
+                .collect();
+            below.sort();
+            below.dedup();
+
+            let idx = stack.bricks.len();
+            let settled = brick.moved_to(rest + 1);
+            let top = rest + settled.height();
+            for (x, y) in footprint {
+                heights[x][y] = (top, Some(idx));
             }
-            let new_brick = cur_brick.drop_1();
-            if !new_brick.does_fit(&lower_bricks, None) {
-                // Can't go any lower, this is the bottom
-                return cur_brick;
+            for b in &below {
+                stack.supports[*b].push(idx);
             }
-            cur_brick = new_brick;
+            stack.bricks.push(settled);
+            stack.supports.push(Vec::new());
+            stack.supported_by.push(below);
         }
+        stack
+    }
+
+    // Safe to take out if everything resting on it has something else holding it up
+    fn safe_to_disintegrate(&self, i: usize) -> bool {
+        self.supports[i].iter().all(|j| self.supported_by[*j].len() > 1)
     }
 
-    // If brick is gone, can any of the other drop?
-    fn safe_to_disintegrate(&self, bricks: &Vec<Brick>) -> bool {
-        for brick in bricks {
-            if brick.eq(self) {
-                continue;
+    // How many other bricks fall when each brick is taken out
+    //  Brick j falls when i goes if every path from the ground up to j goes through i, so i dominates j
+    //  With the ground as node 0, a brick's immediate dominator is the lowest common ancestor of its
+    //  supporters in the dominator tree, and we land bricks in an order where those are already known
+    //  The bricks that fall for i are then just the size of i's subtree
+    fn fall_counts(&self) -> Vec<usize> {
+        let n = self.bricks.len() + 1;
+        let levels = (usize::BITS - n.leading_zeros()) as usize + 1;
+        // up[k][v] is v's 2^k-th ancestor, the ground is its own parent
+        let mut up: Vec<Vec<usize>> = vec![vec![0; n]; levels];
+        let mut depth: Vec<usize> = vec![0; n];
+
+        let lca = |up: &Vec<Vec<usize>>, depth: &Vec<usize>, mut a: usize, mut b: usize| {
+            if depth[a] < depth[b] {
+                std::mem::swap(&mut a, &mut b);
             }
-            let n_brick = brick.drop_1();
-            if n_brick.eq(brick) { 
-                // It didn't actually drop
-                continue;
+            for k in (0..levels).rev() {
+                if depth[a] - depth[b] >= 1 << k {
+                    a = up[k][a];
+                }
+            }
+            if a == b {
+                return a;
+            }
+            for k in (0..levels).rev() {
+                if up[k][a] != up[k][b] {
+                    a = up[k][a];
+                    b = up[k][b];
+                }
             }
-            if n_brick.does_fit(bricks, Some(&self)) {
-                // No this brick can drop
-                println!("Brick {} can drop if {} is removed", brick.index, self.index);
-                return false;
+            up[0][a]
+        };
+
+        for (i, below) in self.supported_by.iter().enumerate() {
+            let node = i + 1;
+            let idom = below.iter().map(|b| b + 1)
+                .reduce(|a, b| lca(&up, &depth, a, b))
+                .unwrap_or(0);
+            depth[node] = depth[idom] + 1;
+            up[0][node] = idom;
+            for k in 1..levels {
+                up[k][node] = up[k - 1][up[k - 1][node]];
             }
         }
-        println!("No bricks can drop if {} is removed", self.index);
-        return true;
+
+        // Dominators always landed first, so add up subtree sizes from the top down
+        let mut size: Vec<usize> = vec![1; n];
+        for node in (1..n).rev() {
+            size[up[0][node]] += size[node];
+        }
+        size[1..].iter().map(|s| s - 1).collect()
     }
 }
 
@@ -134,6 +172,14 @@ impl Day22 {
             items: Vec::new(),
         }
     }
+
+    fn settle(&self) -> Stack {
+        let stack = Stack::settle(&self.items);
+        for brick in &stack.bricks {
+            println!("Dropped Brick: {:?}", brick);
+        }
+        stack
+    }
 }
 
 impl AOCProblem for Day22 {
@@ -147,65 +193,23 @@ impl AOCProblem for Day22 {
     }
     
     fn compute_a(&mut self) -> String {
-        // Sort by lowest, we can ignore higher up bricks
-        self.items.sort_by(|a,b| a.lowest_z().cmp(&b.lowest_z()));
-        let mut dropped = Vec::new();
-        for brick in &self.items {
-            // Lower the brick as far as it can go
-            let n_brick = brick.drop_brick(&dropped);
-            dropped.push(n_brick);
-        }
-        for brick in &dropped {
-            println!("Dropped Brick: {:?}", brick);
-        }
-        dropped.sort_by(|a,b| a.lowest_z().cmp(&b.lowest_z()));
-
-        // For each brick, test if it can be disintegrated
+        let stack = self.settle();
         let mut safe = 0;
-        for brick in &dropped {
-            println!("Testing brick: {:?}", brick);
-            if brick.safe_to_disintegrate(&dropped) {
+        for (i, brick) in stack.bricks.iter().enumerate() {
+            if stack.safe_to_disintegrate(i) {
+                println!("No bricks can drop if {} is removed", brick.index);
                 safe += 1;
             }
         }
         safe.to_string()
     }
 
-    // Cut-n-paste from A, should refactor...
     fn compute_b(&mut self) -> String {
-        // Sort by lowest, we can ignore higher up bricks
-        self.items.sort_by(|a,b| a.lowest_z().cmp(&b.lowest_z()));
-        let mut dropped = Vec::new();
-        for brick in &self.items {
-            // Lower the brick as far as it can go
-            let n_brick = brick.drop_brick(&dropped);
-            dropped.push(n_brick);
-        }
-        for brick in &dropped {
-            println!("Dropped Brick: {:?}", brick);
-        }
-        dropped.sort_by(|a,b| a.lowest_z().cmp(&b.lowest_z()));
-
-        // For each brick, count how many bricks would drop
-        let mut would_drop = 0;
-        for brick in &dropped {
-            let mut drop_count = 0;
-            let mut dropped2: Vec<Brick> = Vec::new();
-            for brick2 in &dropped {
-                if brick2.index == brick.index {
-                    continue;
-                }
-                // Lower the brick as far as it can go
-                let n_brick = brick2.drop_brick(&dropped2);
-                if !n_brick.eq(brick2) {
-                    drop_count += 1;
-                }
-                dropped2.push(n_brick);
-            }
-            println!("For {}, {} bricks would drop", brick.index, drop_count);
-            would_drop += drop_count;
+        let stack = self.settle();
+        let counts = stack.fall_counts();
+        for (brick, count) in stack.bricks.iter().zip(&counts) {
+            println!("For {}, {} bricks would drop", brick.index, count);
         }
-        would_drop.to_string()
+        counts.iter().sum::<usize>().to_string()
     }
 }
-
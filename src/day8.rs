@@ -7,6 +7,100 @@ pub struct Node {
     left: String,
     right: String,
 }
+
+// The path of a single ghost, split into a tail (before it loops) and a cycle
+//  A state is (node, instruction pointer), so a repeated state is guaranteed to be a real cycle
+#[derive(Debug)]
+pub struct GhostCycle {
+    start: String,
+    // Steps taken before entering the cycle
+    tail: u64,
+    // Length of the cycle in steps
+    period: u64,
+    // Steps at which we're on an end node before entering the cycle
+    tail_hits: Vec<u64>,
+    // Offsets from the start of the cycle at which we're on an end node
+    cycle_hits: Vec<u64>,
+}
+
+impl GhostCycle {
+    // Is the ghost on an end node after this many steps?
+    fn is_hit(&self, step: u64) -> bool {
+        if step < self.tail {
+            return self.tail_hits.contains(&step);
+        }
+        self.cycle_hits.contains(&((step - self.tail) % self.period))
+    }
+}
+
+// If every ghost hits an end node exactly once per cycle, at a multiple of its cycle length,
+//  then the answer is just the LCM of the cycle lengths
+fn lcm_fast_path(cycles: &[GhostCycle]) -> Option<u64> {
+    let mut l: u64 = 1;
+    for cycle in cycles {
+        if !cycle.tail_hits.is_empty() || cycle.cycle_hits.len() != 1 {
+            return None;
+        }
+        if (cycle.tail + cycle.cycle_hits[0]) % cycle.period != 0 {
+            return None;
+        }
+        l = num::integer::lcm(l, cycle.period);
+    }
+    // Multiples of the period that fall inside the tail aren't hits
+    if cycles.iter().any(|c| c.tail > l) {
+        return None;
+    }
+    Some(l)
+}
+
+// Combine t = r1 mod m1 and t = r2 mod m2 into a single residue, if they're compatible
+fn crt_pair(r1: i128, m1: i128, r2: i128, m2: i128) -> Option<(i128, i128)> {
+    let egcd = num::Integer::extended_gcd(&m1, &m2);
+    let g = egcd.gcd;
+    if (r2 - r1) % g != 0 {
+        return None;
+    }
+    let m = m1 / g * m2;
+    let k = ((r2 - r1) / g * egcd.x).rem_euclid(m2 / g);
+    Some(((r1 + m1 * k).rem_euclid(m), m))
+}
+
+// Find the first step where every ghost is on an end node at the same time
+fn solve_crt(cycles: &[GhostCycle]) -> Option<u64> {
+    // Any step before the longest tail has to be a tail hit of that ghost, check those first
+    let longest = cycles.iter().max_by_key(|c| c.tail)?;
+    for step in &longest.tail_hits {
+        if cycles.iter().all(|c| c.is_hit(*step)) {
+            return Some(*step);
+        }
+    }
+
+    // After that every ghost is periodic, so fold the residues of each ghost together
+    let mut residues: Vec<(i128, i128)> = vec![(0, 1)];
+    for cycle in cycles {
+        let period = cycle.period as i128;
+        let mut next: Vec<(i128, i128)> = Vec::new();
+        for (r, m) in &residues {
+            for offset in &cycle.cycle_hits {
+                let r2 = ((cycle.tail + offset) as i128).rem_euclid(period);
+                if let Some(combined) = crt_pair(*r, *m, r2, period) {
+                    if !next.contains(&combined) {
+                        next.push(combined);
+                    }
+                }
+            }
+        }
+        residues = next;
+    }
+
+    // Smallest step at or after the longest tail for each residue
+    let lower = longest.tail as i128;
+    residues.iter()
+        .map(|(r, m)| lower + (r - lower).rem_euclid(*m))
+        .min()
+        .map(|s| s as u64)
+}
+
 pub struct Day8 {
     instructions: Vec<char>,
     nodes: HashMap<String, Node>,
@@ -19,6 +113,37 @@ impl Day8 {
             nodes: HashMap::new(),
         }
     }
+
+    // Walk from start until we repeat a (node, instruction pointer) state
+    fn find_cycle(&self, start: &str) -> Option<GhostCycle> {
+        let mut seen: HashMap<(&str, usize), u64> = HashMap::new();
+        let mut hits: Vec<u64> = Vec::new();
+        let mut cur_node = self.nodes.get(start)?;
+        let mut iptr = 0;
+        let mut steps: u64 = 0;
+        loop {
+            if let Some(first) = seen.get(&(&cur_node.label[..], iptr)) {
+                let tail = *first;
+                let period = steps - tail;
+                let tail_hits = hits.iter().filter(|h| **h < tail).copied().collect();
+                let cycle_hits = hits.iter().filter(|h| **h >= tail).map(|h| h - tail).collect();
+                return Some(GhostCycle { start: start.to_string(), tail, period, tail_hits, cycle_hits });
+            }
+            seen.insert((&cur_node.label[..], iptr), steps);
+            if cur_node.label.ends_with("Z") {
+                hits.push(steps);
+            }
+            let step = self.instructions.get(iptr)?;
+            iptr += 1;
+            if iptr >= self.instructions.len() { iptr = 0 };
+            match step {
+                'L' => cur_node = self.nodes.get(&cur_node.left[..])?,
+                'R' => cur_node = self.nodes.get(&cur_node.right[..])?,
+                _ => { eprintln!("Unknown instruction: {}", step); return None; },
+            }
+            steps += 1;
+        }
+    }
 }
 
 impl AOCProblem for Day8 {
@@ -73,68 +198,38 @@ impl AOCProblem for Day8 {
     }
 
     fn compute_b(&mut self) -> String {
-        let mut cur_nodes: Vec<&Node> = Vec::new();
         // Start on every node ending with A
-        for node in &self.nodes {
-            if node.0.ends_with("A") {
-                cur_nodes.push(&node.1);
-            }
-        }
+        let mut starts: Vec<&String> = self.nodes.keys().filter(|l| l.ends_with("A")).collect();
+        starts.sort();
+
         // Brute force takes too long, ugh
-        
-        // Let's do each node 1 by 1 and see how long to find an end, and look for a cycle
-        // Testing shows we have cycles where the instruction pointer is at the same spot, so a real cycle
-        // Compute the lowest common multiplier of all cycle periods.
-        //  Basically, this is "lucky", if you look for a different end node (such as steps ending in B) it won't work
-        //   Since the instruction pointer is at a different spot, it's not really a cycle since we may go a different path
-        //  But for nodes ending in Z as the final, the iptr is at the same spot so it's a real cycle
-        
-        let mut cycle_counts: Vec<u64> = Vec::new();
-
-        for node in cur_nodes {
-            let mut steps = 0;
-            let mut iptr = 0;
-            let mut cur_node = node;
-            let cycle = false;
-            let mut found_end_once = false;
-            let mut cycle_steps = 0;
-            while !cycle {
-                let found_end = cur_node.label.ends_with("Z");
-                if found_end {
-                    if !found_end_once { 
-                        found_end_once = true;
-                        cycle_steps = 0;
-                        println!("{} found end {} for the first time after {} steps with iptr {}", node.label, cur_node.label, steps, iptr);
-                    } else {
-                        println!("{} found {} in {} initial steps then again after {} steps with iptr {}",
-                            node.label, cur_node.label, steps, cycle_steps, iptr);
-                            cycle_counts.push(cycle_steps);
-                        break;
-                    }
-                }
-                let step = self.instructions.get(iptr).unwrap();
-                iptr += 1;
-                if iptr >= self.instructions.len() { iptr = 0 };
-                match step {
-                    'L' => cur_node = self.nodes.get(&cur_node.left[..]).unwrap(),
-                    'R' => cur_node = self.nodes.get(&cur_node.right[..]).unwrap(),
-                    _ => { eprintln!("Unknown instruction: {}", step); return "Error".to_string(); },
-                }
-                if found_end_once { cycle_steps += 1; } else { steps += 1; }
-                //println!("{} -> {} -> {}", prev_node_lbl, step, cur_node.label);
+        // Instead, walk each ghost until it repeats a (node, instruction pointer) state
+        //  That gives us a real cycle, and we record every step in the cycle where we're on an end node
+        let mut cycles: Vec<GhostCycle> = Vec::new();
+        for start in starts {
+            match self.find_cycle(start) {
+                Some(cycle) => {
+                    println!("{} enters a cycle of {} steps after {} steps, end nodes at {:?} then {:?} in the cycle",
+                        cycle.start, cycle.period, cycle.tail, cycle.tail_hits, cycle.cycle_hits);
+                    cycles.push(cycle);
+                },
+                None => return "Error".to_string(),
             }
-            // println!("{} Found end node in {} steps, iptr at {}", node.label, steps, iptr);
         }
 
-        let mut num1: u64 = 0;
-        for ccount in cycle_counts {
-            if num1 == 0 { num1 = ccount; continue; }
-            let l: u64 = num::integer::lcm(ccount, num1);
-            println!("LCM of {} and {} is {}", ccount, num1, l);
-            num1 = l;
+        // The LCM trick is "lucky", it only works when each ghost hits exactly one end node per cycle
+        //  and that hit lands on a multiple of the cycle length.  Check that before using it
+        if let Some(l) = lcm_fast_path(&cycles) {
+            println!("LCM fast path applies");
+            return l.to_string();
         }
 
-        return num1.to_string();
+        // Otherwise, combine every end node offset of every ghost with the Chinese Remainder Theorem
+        println!("LCM fast path does not apply, using CRT");
+        match solve_crt(&cycles) {
+            Some(steps) => steps.to_string(),
+            None => "No solution".to_string(),
+        }
     }
 
     /*
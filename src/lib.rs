@@ -4,7 +4,7 @@
 //   Then each day, we can add a new AOCProblem implementation only
 //  Until we do that refactor, we'll just add another line to the matches
 
-use std::{fs, error::Error};
+use std::{fs, error::Error, collections::HashMap, str::FromStr};
 
 mod day1;
 mod day2;
@@ -32,6 +32,11 @@ mod day23;
 mod day24;
 mod day25;
 
+mod cycle;
+mod pipes;
+mod sequence;
+mod shortest_path;
+
 use day1::Day1;
 use day2::Day2;
 use day3::Day3;
@@ -68,20 +73,24 @@ pub struct Config {
 
     // True if we're running the test inputs, false if we're running the final input
     pub test_input: bool,
+
+    // Optional key=value parameters after the required ones, days can look up what they need
+    pub options: HashMap<String, String>,
 }
 
 fn usage() {
-    eprintln!("Usage: day variant test_input");
+    eprintln!("Usage: day variant test_input [key=value ...]");
     eprintln!("  day: 1-25 (which AOC day to run)");
     eprintln!("  variant: a | b");
     eprintln!("  test_input: test | final");
     eprintln!("    test input is expected at testX.txt");
     eprintln!("    final input is expected at finalX.txt");
+    eprintln!("  key=value: optional parameters for the day, e.g. expansion=10");
 }
 
 impl Config {
     pub fn build(args: &[String]) -> Result<Config, &'static str> {
-        if args.len() != 4 {
+        if args.len() < 4 {
             usage();
             return Err("Incorrect number of command line parameters");
         }
@@ -104,12 +113,38 @@ impl Config {
             "final" => false,
             _ => return Err("Expecting test or final for test_input"),
         };
+
+        let mut options = HashMap::new();
+        for arg in &args[4..] {
+            match arg.split_once('=') {
+                Some((key, value)) => { options.insert(key.to_string(), value.to_string()); },
+                None => {
+                    usage();
+                    return Err("Expecting optional parameters as key=value");
+                },
+            }
+        }
             
-        Ok(Config { day, variant, test_input })
+        Ok(Config { day, variant, test_input, options })
+    }
+
+    // Look up an optional parameter, None if it's missing, Err if it's there but doesn't parse
+    pub fn get_option<T: FromStr>(&self, key: &str) -> Result<Option<T>, String> {
+        match self.options.get(key) {
+            Some(value) => match value.parse::<T>() {
+                Ok(v) => Ok(Some(v)),
+                Err(_) => Err(format!("Unable to parse option {}={}", key, value)),
+            },
+            None => Ok(None),
+        }
     }
 }
 
 pub trait AOCProblem {
+    // Read the key=value options, once before any input, Err if one of them is bad
+    fn configure(&mut self, _config: &Config) -> Result<(), String> {
+        Ok(())
+    }
     fn handle_line(&mut self, line: &str, config: &Config);
     fn compute_a(&mut self) -> String;
     fn compute_b(&mut self) -> String;
@@ -158,6 +193,8 @@ pub fn run(config: Config) -> Result<String, Box<dyn Error>> {
         _ => return Err("Day not yet handled".into()),
     }
     
+    (*day).configure(&config)?;
+
     // Read the input, pass it to the AOCProblem trait
     for line in contents.lines() {
         (*day).handle_line(line, &config);
@@ -0,0 +1,337 @@
+// Pipe network analysis, generalized from Day10
+//  Each tile is a bitmask of the directions it connects to, so any tile set can be parsed
+//  The start tile's shape is inferred from which neighbors connect back to it
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Write;
+
+pub const NORTH: u8 = 1;
+pub const SOUTH: u8 = 2;
+pub const EAST: u8 = 4;
+pub const WEST: u8 = 8;
+
+const DIRECTIONS: [u8; 4] = [NORTH, SOUTH, EAST, WEST];
+
+fn opposite(dir: u8) -> u8 {
+    match dir {
+        NORTH => SOUTH,
+        SOUTH => NORTH,
+        EAST => WEST,
+        _ => EAST,
+    }
+}
+
+// Glyphs for tiles that aren't on the main loop
+const INSIDE: char = '▓';
+const OUTSIDE: char = '·';
+
+// ANSI colors for the terminal version of the render
+const COLOR_START: &str = "\x1b[1;31m";
+const COLOR_LOOP: &str = "\x1b[1;36m";
+const COLOR_INSIDE: &str = "\x1b[32m";
+const COLOR_OUTSIDE: &str = "\x1b[2m";
+const COLOR_RESET: &str = "\x1b[0m";
+
+// Box drawing character for a pipe
+fn box_char(connections: u8) -> char {
+    match connections {
+        c if c == NORTH | SOUTH => '│',
+        c if c == EAST | WEST => '─',
+        c if c == NORTH | EAST => '└',
+        c if c == NORTH | WEST => '┘',
+        c if c == SOUTH | WEST => '┐',
+        c if c == SOUTH | EAST => '┌',
+        // Junctions, a custom tile set can branch 3 or 4 ways
+        c if c == NORTH | SOUTH | EAST => '├',
+        c if c == NORTH | SOUTH | WEST => '┤',
+        c if c == SOUTH | EAST | WEST => '┬',
+        c if c == NORTH | EAST | WEST => '┴',
+        c if c == NORTH | SOUTH | EAST | WEST => '┼',
+        _ => '?',
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct Location {
+    pub x: usize,
+    pub y: usize,
+}
+
+// Which characters are pipes, and which directions they connect
+pub struct TileSet {
+    tiles: HashMap<char, u8>,
+    start: char,
+    ground: char,
+}
+
+impl TileSet {
+    pub fn new(start: char, ground: char) -> TileSet {
+        TileSet {
+            tiles: HashMap::new(),
+            start,
+            ground,
+        }
+    }
+
+    // The tiles from the puzzle
+    pub fn standard() -> TileSet {
+        let mut tiles = TileSet::new('S', '.');
+        tiles.add_tile('|', NORTH | SOUTH);
+        tiles.add_tile('-', EAST | WEST);
+        tiles.add_tile('L', NORTH | EAST);
+        tiles.add_tile('J', NORTH | WEST);
+        tiles.add_tile('7', SOUTH | WEST);
+        tiles.add_tile('F', SOUTH | EAST);
+        tiles
+    }
+
+    pub fn add_tile(&mut self, symbol: char, connections: u8) {
+        self.tiles.insert(symbol, connections);
+    }
+
+    // Which symbol has exactly these connections, if any
+    pub fn symbol_for(&self, connections: u8) -> Option<char> {
+        self.tiles.iter().find(|(_, c)| **c == connections).map(|(s, _)| *s)
+    }
+}
+
+pub struct PipeNetwork {
+    tiles: TileSet,
+    // Connections for each tile, 0 for ground
+    grid: Vec<Vec<u8>>,
+    start: Location,
+    // Tiles on the main loop, in walking order starting at the start tile
+    main_loop: Vec<Location>,
+    on_loop: Vec<Vec<bool>>,
+}
+
+impl PipeNetwork {
+    pub fn parse(lines: &[String], tiles: TileSet) -> Result<PipeNetwork, String> {
+        let mut grid: Vec<Vec<u8>> = Vec::new();
+        let mut start: Option<Location> = None;
+        for (y, line) in lines.iter().enumerate() {
+            let mut row: Vec<u8> = Vec::new();
+            for (x, c) in line.chars().enumerate() {
+                if c == tiles.start {
+                    if start.is_some() {
+                        return Err(format!("Found a second start tile at {},{}", x, y));
+                    }
+                    start = Some(Location { x, y });
+                    row.push(0);
+                } else if c == tiles.ground {
+                    row.push(0);
+                } else {
+                    match tiles.tiles.get(&c) {
+                        Some(conn) => row.push(*conn),
+                        None => return Err(format!("Unknown tile {} at {},{}", c, x, y)),
+                    }
+                }
+            }
+            // Everything assumes a rectangle, step only checks the width of the row it's on
+            if let Some(first) = grid.first() {
+                if row.len() != first.len() {
+                    return Err(format!("Row {} is {} tiles wide, expected {}", y, row.len(), first.len()));
+                }
+            }
+            grid.push(row);
+        }
+
+        let start = start.ok_or("No start tile found")?;
+        let mut network = PipeNetwork {
+            tiles,
+            grid,
+            start,
+            main_loop: Vec::new(),
+            on_loop: Vec::new(),
+        };
+        network.infer_start()?;
+        Ok(network)
+    }
+
+    pub fn width(&self) -> usize {
+        self.grid.first().map_or(0, |r| r.len())
+    }
+
+    pub fn height(&self) -> usize {
+        self.grid.len()
+    }
+
+    pub fn start(&self) -> Location {
+        self.start
+    }
+
+    pub fn connections(&self, loc: &Location) -> u8 {
+        self.grid[loc.y][loc.x]
+    }
+
+    pub fn main_loop(&self) -> &[Location] {
+        &self.main_loop
+    }
+
+    // What the start tile really is, if the tile set has a symbol for it
+    pub fn start_symbol(&self) -> Option<char> {
+        self.tiles.symbol_for(self.connections(&self.start))
+    }
+
+    fn step(&self, loc: &Location, dir: u8) -> Option<Location> {
+        match dir {
+            NORTH if loc.y > 0 => Some(Location { x: loc.x, y: loc.y - 1 }),
+            SOUTH if loc.y + 1 < self.height() => Some(Location { x: loc.x, y: loc.y + 1 }),
+            WEST if loc.x > 0 => Some(Location { x: loc.x - 1, y: loc.y }),
+            EAST if loc.x + 1 < self.grid[loc.y].len() => Some(Location { x: loc.x + 1, y: loc.y }),
+            _ => None,
+        }
+    }
+
+    // Does the neighbor in this direction connect back to loc?
+    fn connects_back(&self, loc: &Location, dir: u8) -> bool {
+        match self.step(loc, dir) {
+            Some(next) => self.connections(&next) & opposite(dir) != 0,
+            None => false,
+        }
+    }
+
+    // Figure out which pipe S is from its neighbors
+    //  If more than two neighbors connect, try each pair until one closes a loop
+    fn infer_start(&mut self) -> Result<(), String> {
+        let candidates: Vec<u8> = DIRECTIONS.iter()
+            .filter(|d| self.connects_back(&self.start, **d))
+            .copied()
+            .collect();
+        for (i, d1) in candidates.iter().enumerate() {
+            for d2 in &candidates[i + 1..] {
+                self.grid[self.start.y][self.start.x] = d1 | d2;
+                if let Some(main_loop) = self.walk_loop() {
+                    self.on_loop = self.grid.iter().map(|r| vec![false; r.len()]).collect();
+                    for loc in &main_loop {
+                        self.on_loop[loc.y][loc.x] = true;
+                    }
+                    self.main_loop = main_loop;
+                    return Ok(());
+                }
+            }
+        }
+        self.grid[self.start.y][self.start.x] = 0;
+        Err(format!("Can't figure out what S is, connecting neighbors: {:?}", candidates))
+    }
+
+    // Walk from the start until we get back to it, None if the pipe leads somewhere else
+    fn walk_loop(&self) -> Option<Vec<Location>> {
+        let start_conn = self.connections(&self.start);
+        let mut dir = *DIRECTIONS.iter().find(|d| start_conn & **d != 0)?;
+        let mut loc = self.start;
+        let mut path: Vec<Location> = Vec::new();
+        loop {
+            path.push(loc);
+            let next = self.step(&loc, dir)?;
+            let conn = self.connections(&next);
+            if conn & opposite(dir) == 0 {
+                return None;
+            }
+            if next == self.start {
+                return Some(path);
+            }
+            // Exit the way we didn't come in
+            let exits = conn & !opposite(dir);
+            dir = *DIRECTIONS.iter().find(|d| exits & **d != 0)?;
+            loc = next;
+            if path.len() > self.width() * self.height() {
+                return None;
+            }
+        }
+    }
+
+    // Pipe tiles that aren't part of the main loop
+    pub fn junk(&self) -> Vec<Location> {
+        let mut junk: Vec<Location> = Vec::new();
+        for (y, row) in self.grid.iter().enumerate() {
+            for (x, conn) in row.iter().enumerate() {
+                if *conn != 0 && !self.on_loop[y][x] {
+                    junk.push(Location { x, y });
+                }
+            }
+        }
+        junk
+    }
+
+    // Which tiles are enclosed by the main loop, using the line crossing algorithm
+    //  Draw a line from west to east, and count how many times we cross the loop boundary
+    //  Only count loop tiles that connect north, so a horizontal run with both corners on the same
+    //  side counts 0 or 2 crossings, and one with opposite corners counts 1
+    pub fn enclosed(&self) -> Vec<Vec<bool>> {
+        let mut inside_map: Vec<Vec<bool>> = Vec::new();
+        for (y, row) in self.grid.iter().enumerate() {
+            let mut inside = false;
+            let mut inside_row: Vec<bool> = Vec::new();
+            for (x, conn) in row.iter().enumerate() {
+                if self.on_loop[y][x] {
+                    if conn & NORTH != 0 {
+                        inside = !inside;
+                    }
+                    inside_row.push(false);
+                } else {
+                    inside_row.push(inside);
+                }
+            }
+            inside_map.push(inside_row);
+        }
+        inside_map
+    }
+
+    pub fn count_enclosed_scanline(&self) -> usize {
+        self.enclosed().iter().map(|r| r.iter().filter(|i| **i).count()).sum()
+    }
+
+    // Twice the area enclosed by the loop, through the tile centers
+    pub fn shoelace_area_x2(&self) -> i64 {
+        let n = self.main_loop.len();
+        let mut sum: i64 = 0;
+        for i in 0..n {
+            let a = self.main_loop[i];
+            let b = self.main_loop[(i + 1) % n];
+            sum += a.x as i64 * b.y as i64 - b.x as i64 * a.y as i64;
+        }
+        sum.abs()
+    }
+
+    // Pick's theorem: A = i + b/2 - 1, so the interior points are i = A - b/2 + 1
+    pub fn count_enclosed_pick(&self) -> i64 {
+        let boundary = self.main_loop.len() as i64;
+        (self.shoelace_area_x2() - boundary) / 2 + 1
+    }
+
+    // Draw the main loop with box drawing characters, and mark enclosed and outside tiles
+    //  Anything not on the main loop (including junk pipes) is drawn as inside or outside
+    pub fn render(&self, color: bool) -> String {
+        let enclosed = self.enclosed();
+        let mut out = String::new();
+        for (y, row) in self.grid.iter().enumerate() {
+            for (x, conn) in row.iter().enumerate() {
+                let (glyph, code) = if self.on_loop[y][x] {
+                    let code = if self.start == (Location { x, y }) { COLOR_START } else { COLOR_LOOP };
+                    (box_char(*conn), code)
+                } else if enclosed[y][x] {
+                    (INSIDE, COLOR_INSIDE)
+                } else {
+                    (OUTSIDE, COLOR_OUTSIDE)
+                };
+                if color {
+                    out.push_str(code);
+                    out.push(glyph);
+                    out.push_str(COLOR_RESET);
+                } else {
+                    out.push(glyph);
+                }
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    // Write the plain (no color) render to a file
+    pub fn write_render(&self, filename: &str) -> std::io::Result<()> {
+        let mut ofile = File::create(filename)?;
+        write!(ofile, "{}", self.render(false))
+    }
+}
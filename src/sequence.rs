@@ -0,0 +1,126 @@
+// Polynomial sequence fitting, generalized from the Day9 difference tables
+//  A sequence of n values is polynomial of degree d if the (d+1)th difference row is all zeros
+//  We keep the first value of each difference row, that's the Newton forward-difference form:
+//    f(x) = sum over k of diff_k * C(x, k)
+//  where x is the index into the original sequence (0 is the first value, -1 is the one before it)
+
+use num::rational::Ratio;
+use num::{Zero, One};
+
+#[derive(Debug)]
+pub struct SequenceFit {
+    // First value of each difference row, up to and including the last non-zero row
+    leading: Vec<i128>,
+    // Number of values in the original sequence
+    len: usize,
+}
+
+// Build the rows of the difference table, stopping once we hit an all zero row
+//  The last row returned is the all zero row, if we found one
+pub fn difference_table(seq: &[i64]) -> Vec<Vec<i128>> {
+    let mut rows: Vec<Vec<i128>> = vec![seq.iter().map(|v| *v as i128).collect()];
+    loop {
+        let last = rows.last().unwrap();
+        if last.iter().all(|v| *v == 0) || last.len() < 2 {
+            return rows;
+        }
+        let next: Vec<i128> = last.windows(2).map(|w| w[1] - w[0]).collect();
+        rows.push(next);
+    }
+}
+
+impl SequenceFit {
+    // Fit the exact polynomial through the sequence, given its difference_table
+    //  Errors if we run out of data before reaching an all zero difference row
+    //  (n points always fit a degree n-1 polynomial, so we need at least one row to confirm it)
+    pub fn from_table(rows: &[Vec<i128>]) -> Result<SequenceFit, &'static str> {
+        if rows.is_empty() || rows[0].is_empty() {
+            return Err("Can't fit an empty sequence");
+        }
+        let last = rows.last().unwrap();
+        if last.is_empty() || !last.iter().all(|v| *v == 0) {
+            return Err("Sequence is not polynomial within the available data");
+        }
+
+        // Drop the zero row, and any zero rows above it (an all zero sequence is degree 0)
+        let mut leading: Vec<i128> = rows[..rows.len() - 1].iter().map(|r| r[0]).collect();
+        if leading.is_empty() {
+            leading.push(0);
+        }
+        Ok(SequenceFit { leading, len: rows[0].len() })
+    }
+
+    pub fn degree(&self) -> usize {
+        self.leading.len() - 1
+    }
+
+    // Value at index x of the sequence, x can be negative or past the end
+    pub fn value_at(&self, x: i64) -> i128 {
+        let x = x as i128;
+        let mut val: i128 = 0;
+        // C(x, k), built up incrementally.  C(x, k-1) * (x-k+1) is always divisible by k
+        let mut binom: i128 = 1;
+        for (k, diff) in self.leading.iter().enumerate() {
+            if k > 0 {
+                binom = binom * (x - k as i128 + 1) / k as i128;
+            }
+            val += diff * binom;
+        }
+        val
+    }
+
+    // The next steps values after the end of the sequence
+    pub fn extrapolate_forward(&self, steps: usize) -> Vec<i128> {
+        (0..steps).map(|i| self.value_at((self.len + i) as i64)).collect()
+    }
+
+    // The steps values before the start of the sequence, closest first
+    pub fn extrapolate_backward(&self, steps: usize) -> Vec<i128> {
+        (1..=steps).map(|i| self.value_at(-(i as i64))).collect()
+    }
+
+    // Expand the Newton form into plain coefficients, coeffs[i] goes with x^i
+    pub fn coefficients(&self) -> Vec<Ratio<i128>> {
+        let mut coeffs: Vec<Ratio<i128>> = vec![Ratio::zero(); self.leading.len()];
+        // x(x-1)...(x-k+1), as coefficients
+        let mut falling: Vec<Ratio<i128>> = vec![Ratio::one()];
+        let mut factorial: i128 = 1;
+        for (k, diff) in self.leading.iter().enumerate() {
+            if k > 0 {
+                factorial *= k as i128;
+                // Multiply falling by (x - (k-1))
+                let shift = Ratio::from_integer(k as i128 - 1);
+                let mut next = vec![Ratio::zero(); falling.len() + 1];
+                for (i, c) in falling.iter().enumerate() {
+                    next[i + 1] += c;
+                    next[i] -= c * shift;
+                }
+                falling = next;
+            }
+            let scale = Ratio::new(*diff, factorial);
+            for (i, c) in falling.iter().enumerate() {
+                coeffs[i] += c * scale;
+            }
+        }
+        coeffs
+    }
+
+    // Human readable polynomial, highest power first
+    pub fn polynomial_string(&self) -> String {
+        let mut terms: Vec<String> = Vec::new();
+        for (i, c) in self.coefficients().iter().enumerate().rev() {
+            if c.is_zero() {
+                continue;
+            }
+            match i {
+                0 => terms.push(format!("{}", c)),
+                1 => terms.push(format!("({})x", c)),
+                _ => terms.push(format!("({})x^{}", c, i)),
+            }
+        }
+        if terms.is_empty() {
+            return "0".to_string();
+        }
+        terms.join(" + ")
+    }
+}
@@ -0,0 +1,115 @@
+// Shortest paths for graphs with small non-negative integer weights (like grid days where moving
+//  costs a single digit), using Dijkstra with a bucket queue instead of a binary heap (Dial's algorithm)
+//  Every queued node is at most max_weight past the current distance, so max_weight + 1 buckets in a
+//  ring are enough, and popping the next node is just moving to the next non-empty bucket
+//  Same shape as pathfinding::prelude::dijkstra, so it can be swapped in
+
+use std::collections::HashMap;
+use std::hash::{BuildHasherDefault, Hash, Hasher};
+
+// Multiply and rotate hasher (the same idea as FxHash)
+//  The default SipHash is slow for small keys like grid positions, and we don't need DoS resistance
+#[derive(Default)]
+struct FastHasher {
+    hash: u64,
+}
+
+impl FastHasher {
+    fn add(&mut self, word: u64) {
+        self.hash = (self.hash.rotate_left(5) ^ word).wrapping_mul(0x517cc1b727220a95);
+    }
+}
+
+impl Hasher for FastHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.add(*b as u64);
+        }
+    }
+
+    fn write_u8(&mut self, i: u8) { self.add(i as u64); }
+    fn write_u32(&mut self, i: u32) { self.add(i as u64); }
+    fn write_u64(&mut self, i: u64) { self.add(i); }
+    fn write_usize(&mut self, i: usize) { self.add(i as u64); }
+
+    fn finish(&self) -> u64 {
+        self.hash
+    }
+}
+
+type FastMap<K, V> = HashMap<K, V, BuildHasherDefault<FastHasher>>;
+
+pub fn dijkstra_buckets<N, FN, IN, FS>(start: &N, max_weight: usize, mut successors: FN, mut success: FS)
+    -> Option<(Vec<N>, usize)>
+    where N: Eq + Hash + Clone,
+          FN: FnMut(&N) -> IN,
+          IN: IntoIterator<Item = (N, usize)>,
+          FS: FnMut(&N) -> bool {
+    // Nodes get an index the first time we see them
+    let mut indexes: FastMap<N, usize> = FastMap::default();
+    let mut nodes: Vec<N> = Vec::new();
+    let mut dist: Vec<usize> = Vec::new();
+    let mut parent: Vec<usize> = Vec::new();
+    let mut done: Vec<bool> = Vec::new();
+
+    let ring = max_weight + 1;
+    let mut buckets: Vec<Vec<usize>> = vec![Vec::new(); ring];
+    let mut queued = 1;
+
+    indexes.insert(start.clone(), 0);
+    nodes.push(start.clone());
+    dist.push(0);
+    parent.push(0);
+    done.push(false);
+    buckets[0].push(0);
+
+    let mut cur = 0;
+    while queued > 0 {
+        let bucket = cur % ring;
+        let idx = match buckets[bucket].pop() {
+            Some(i) => i,
+            None => { cur += 1; continue; },
+        };
+        queued -= 1;
+        // Stale entry, we already found a shorter way here
+        if done[idx] || dist[idx] != cur { continue; }
+        done[idx] = true;
+
+        if success(&nodes[idx]) {
+            let mut path: Vec<N> = vec![nodes[idx].clone()];
+            let mut p = idx;
+            while p != 0 {
+                p = parent[p];
+                path.push(nodes[p].clone());
+            }
+            path.reverse();
+            return Some((path, cur));
+        }
+
+        for (next, weight) in successors(&nodes[idx]) {
+            if weight > max_weight {
+                panic!("Edge weight {} is bigger than the bucket queue's max weight {}", weight, max_weight);
+            }
+            let new_dist = cur + weight;
+            let next_idx = match indexes.get(&next) {
+                Some(i) => *i,
+                None => {
+                    let i = nodes.len();
+                    indexes.insert(next.clone(), i);
+                    nodes.push(next);
+                    dist.push(usize::MAX);
+                    parent.push(idx);
+                    done.push(false);
+                    i
+                },
+            };
+            if new_dist < dist[next_idx] {
+                dist[next_idx] = new_dist;
+                parent[next_idx] = idx;
+                buckets[new_dist % ring].push(next_idx);
+                queued += 1;
+            }
+        }
+    }
+    None
+}